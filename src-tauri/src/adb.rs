@@ -6,10 +6,20 @@ use std::fs;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Read, Write};
 use std::os::windows::process::CommandExt;
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
 use zip::write::FileOptions;
 
+use crate::adb_backup;
+use crate::archive;
+use crate::backup_manifest::{self, BackupManifest};
+use crate::backup_naming;
+use crate::package_info;
+use crate::permissions;
+use crate::rollback;
+use crate::root_data;
+use crate::splits;
+
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 const MIN_VALID_APK_SIZE: u64 = 1024; // P3 #11: Magic number extracted to constant
 
@@ -190,20 +200,266 @@ pub fn get_packages(device_id: &str) -> Result<Vec<AppPackage>, String> {
     Ok(packages)
 }
 
+/// Optional components of a backup besides the APK.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct BackupOptions {
+    /// Pull `/sdcard/Android/obb/<pkg>`
+    pub include_obb: bool,
+    /// Back up `/data/data/<pkg>` (root, or `adb backup` fallback)
+    pub include_data: bool,
+    /// Without root, fall back to `adb backup -noapk` for app data. Off by
+    /// default: every run waits for a confirmation on the device screen.
+    pub adb_backup_fallback: bool,
+    /// Back up `/sdcard/Android/data/<pkg>` (downloads, offline maps, media caches)
+    pub include_external_data: bool,
+    /// Paths relative to `Android/data/<pkg>` to leave out (e.g. "cache")
+    pub external_data_excludes: Vec<String>,
+    /// Password for encrypted `adb backup` streams (non-root data fallback).
+    /// Needed when the device has a desktop backup password set.
+    /// Never written to disk (e.g. with a persisted backup queue).
+    #[serde(skip_serializing)]
+    pub adb_backup_password: Option<String>,
+    /// Android user whose data dirs are backed up (root); 0 when not set
+    pub user: Option<u32>,
+}
+
+impl Default for BackupOptions {
+    fn default() -> Self {
+        BackupOptions {
+            include_obb: true,
+            include_data: true,
+            adb_backup_fallback: false,
+            include_external_data: false,
+            external_data_excludes: Vec::new(),
+            adb_backup_password: None,
+            user: None,
+        }
+    }
+}
+
+/// Normalizes `external_data_excludes` ("/cache/" -> "cache"). They end up in
+/// shell commands, so only plain relative paths are accepted.
+fn normalize_excludes(excludes: &[String]) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for exclude in excludes {
+        let path = exclude.trim().trim_matches('/');
+        let safe = !path.is_empty()
+            && path
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | ' ' | '/'))
+            && path
+                .split('/')
+                .all(|p| !p.is_empty() && p != "." && p != "..");
+        if !safe {
+            return Err(format!("Invalid external data exclude: {}", exclude));
+        }
+        normalized.push(path.to_string());
+    }
+    // An exclude inside another one is already covered
+    let all = normalized.clone();
+    normalized.retain(|e| {
+        !all.iter()
+            .any(|other| other != e && e.starts_with(&format!("{}/", other)))
+    });
+    normalized.sort();
+    normalized.dedup();
+    Ok(normalized)
+}
+
+/// Size of `/sdcard/Android/data/<pkg>` in bytes without `excludes`. Uses
+/// root if the shell user can't read it.
+pub fn get_external_data_size(
+    device_id: &str,
+    package_name: &str,
+    excludes: &[String],
+) -> Result<u64, String> {
+    let excludes = normalize_excludes(excludes)?;
+    let size_with = |base: &str, su: bool| -> Option<u64> {
+        let root = format!("{}/{}", base, package_name);
+        let mut du_cmd = format!("du -sk '{}'", root);
+        for exclude in &excludes {
+            du_cmd.push_str(&format!(" '{}/{}'", root, exclude));
+        }
+        // Missing excluded paths make du fail; their lines are simply absent
+        du_cmd.push_str(" 2>/dev/null; true");
+        let output = if su {
+            run_command(&["-s", device_id, "shell", "su", "-c", &du_cmd])
+        } else {
+            run_command(&["-s", device_id, "shell", &du_cmd])
+        }
+        .ok()?;
+
+        let mut total = None;
+        let mut excluded = 0;
+        for line in output.lines() {
+            let mut parts = line.splitn(2, char::is_whitespace);
+            let (Some(kb), Some(path)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            let Ok(kb) = kb.parse::<u64>() else {
+                continue;
+            };
+            if path.trim() == root {
+                total = Some(kb);
+            } else {
+                excluded += kb;
+            }
+        }
+        total.map(|kb| kb.saturating_sub(excluded) * 1024)
+    };
+
+    size_with("/sdcard/Android/data", false)
+        .or_else(|| size_with("/data/media/0/Android/data", true))
+        .ok_or_else(|| "Could not determine external data size".to_string())
+}
+
+/// Pulls `remote` into `local` without `excludes` (relative to `remote`).
+/// Only the folders leading to an excluded path are listed; everything else
+/// is pulled as a whole.
+fn pull_filtered(device_id: &str, remote: &str, local: &Path, excludes: &[String]) -> bool {
+    let local_str = local.to_string_lossy().to_string();
+    if excludes.is_empty() {
+        return run_command(&["-s", device_id, "pull", "-a", remote, &local_str]).is_ok();
+    }
+
+    let Ok(listing) = run_command(&["-s", device_id, "shell", &format!("ls -1A '{}'", remote)])
+    else {
+        return false;
+    };
+    if fs::create_dir_all(local).is_err() {
+        return false;
+    }
+
+    for name in listing.lines().filter(|l| !l.is_empty()) {
+        if excludes.iter().any(|e| e == name) {
+            continue;
+        }
+        let prefix = format!("{}/", name);
+        let nested: Vec<String> = excludes
+            .iter()
+            .filter_map(|e| e.strip_prefix(&prefix))
+            .map(|e| e.to_string())
+            .collect();
+        let child_remote = format!("{}/{}", remote, name);
+        if !pull_filtered(device_id, &child_remote, &local.join(name), &nested) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Pulls `/sdcard/Android/data/<pkg>` into `local_dir`, leaving `excludes`
+/// (already normalized) on the device.
+///
+/// Android 11+ hides other apps' `Android/data` folders from the shell user on
+/// many builds, so if a plain `adb pull` fails we copy through root to a
+/// readable staging folder first.
+fn pull_external_data(
+    device_id: &str,
+    package_name: &str,
+    local_dir: &Path,
+    excludes: &[String],
+) -> bool {
+    let remote_path = format!("/sdcard/Android/data/{}", package_name);
+    let local_str = local_dir.to_string_lossy().to_string();
+
+    if run_command(&["-s", device_id, "shell", "ls", "-d", &remote_path]).is_ok()
+        && pull_filtered(device_id, &remote_path, local_dir, excludes)
+    {
+        return true;
+    }
+
+    // Partial pull leftovers
+    let _ = fs::remove_dir_all(local_dir);
+
+    if !is_device_rooted(device_id) {
+        return false;
+    }
+
+    let staging = format!("/data/local/tmp/easyadb_extdata_{}", package_name);
+    let exclude_args: String = excludes
+        .iter()
+        .map(|e| format!(" --exclude='{}/{}'", package_name, e))
+        .collect();
+    // tar keeps timestamps like `cp -a` and skips the excluded paths
+    let copy_cmd = format!(
+        "rm -rf {0} && mkdir -p {0} && tar -cf - -C /data/media/0/Android/data{2} {1} | tar -xf - -C {0} && chmod -R a+rX {0}",
+        staging, package_name, exclude_args
+    );
+    let staged = format!("{}/{}", staging, package_name);
+    let pulled = run_command(&["-s", device_id, "shell", "su", "-c", &copy_cmd]).is_ok()
+        && run_command(&["-s", device_id, "pull", "-a", &staged, &local_str]).is_ok();
+
+    let _ = run_command(&[
+        "-s",
+        device_id,
+        "shell",
+        "su",
+        "-c",
+        &format!("rm -rf {}", staging),
+    ]);
+    pulled
+}
+
+/// Pushes a local folder back to `/sdcard/Android/data/<pkg>`, through root if needed.
+fn push_external_data(device_id: &str, package_name: &str, local_dir: &Path) -> bool {
+    let remote_path = format!("/sdcard/Android/data/{}", package_name);
+    if archive::push_directory(device_id, local_dir, &remote_path).is_ok() {
+        return true;
+    }
+
+    if !is_device_rooted(device_id) {
+        return false;
+    }
+
+    // Stage in /data/local/tmp, then copy into place with the folder's owner
+    let staging = format!("/data/local/tmp/easyadb_extdata_{}", package_name);
+    let target = format!("/data/media/0/Android/data/{}", package_name);
+    let _ = run_command(&["-s", device_id, "shell", "rm", "-rf", &staging]);
+    if archive::push_directory(device_id, local_dir, &staging).is_err() {
+        return false;
+    }
+    let copy_cmd = format!(
+        "mkdir -p {1} && cp -a {0}/. {1}/ && chown -R $(stat -c '%u:%g' {1}) {1}; rm -rf {0}",
+        staging, target
+    );
+    run_command(&["-s", device_id, "shell", "su", "-c", &copy_cmd]).is_ok()
+}
+
 pub fn backup_package(
     device_id: &str,
     package: AppPackage,
     dest_path: PathBuf,
+    backup_options: &BackupOptions,
 ) -> Result<String, String> {
-    // 1. Create a temporary folder for this backup
-    let temp_backup_dir = env::temp_dir()
-        .join("easyadb_processing")
-        .join(&package.name);
+    // 1. Create a temporary folder for this backup. Queue workers, schedules and
+    // store backups can run at the same time, so every call gets its own folder.
+    let temp_backup_dir = env::temp_dir().join("easyadb_processing").join(format!(
+        "{}_{}_{}",
+        backup_naming::sanitize_component(device_id),
+        package.name,
+        chrono::Local::now().format("%Y%m%d_%H%M%S%9f")
+    ));
+
+    let result = backup_package_in(
+        device_id,
+        package,
+        dest_path,
+        backup_options,
+        &temp_backup_dir,
+    );
+    let _ = fs::remove_dir_all(&temp_backup_dir);
+    result
+}
 
-    // Clean start
-    if temp_backup_dir.exists() {
-        fs::remove_dir_all(&temp_backup_dir).map_err(|e| e.to_string())?;
-    }
+fn backup_package_in(
+    device_id: &str,
+    package: AppPackage,
+    dest_path: PathBuf,
+    backup_options: &BackupOptions,
+    temp_backup_dir: &Path,
+) -> Result<String, String> {
     let apks_dir = temp_backup_dir.join("apks");
     let obb_dir = temp_backup_dir.join("obb");
     fs::create_dir_all(&apks_dir).map_err(|e| e.to_string())?;
@@ -247,12 +503,28 @@ pub fn backup_package(
         }
     }
 
+    // 2b. Inspect pulled APKs (native ABIs + signing certificate) and query dumpsys
+    let mut pulled_apks = Vec::new();
+    for entry in fs::read_dir(&apks_dir)
+        .map_err(|e| e.to_string())?
+        .flatten()
+    {
+        pulled_apks.push(entry.path());
+    }
+    let (native_abis, signing_cert) = package_info::inspect_apks(&pulled_apks);
+    let mut app_details = package_info::query_package_details(device_id, &package.name);
+    app_details.native_abis = native_abis;
+    app_details.signing_cert_sha256 = signing_cert;
+    let source_device = package_info::query_source_device(device_id);
+    let permission_state = permissions::capture(device_id, &package.name);
+    let has_permissions = !permission_state.is_empty();
+
     // 3. Check & Pull OBB
     // OBB path: /sdcard/Android/obb/<package_name>
     let remote_obb_path = format!("/sdcard/Android/obb/{}", package.name);
     let obb_check = run_command(&["-s", device_id, "shell", "ls", "-d", &remote_obb_path]);
 
-    let has_obb = if obb_check.is_ok() {
+    let has_obb = if backup_options.include_obb && obb_check.is_ok() {
         fs::create_dir_all(&obb_dir).map_err(|e| e.to_string())?;
         // Simpler approach:
         // ADB Pull <remote> <local>
@@ -264,10 +536,12 @@ pub fn backup_package(
             let _ = fs::remove_dir_all(&final_obb_target);
         }
 
+        // -a: keep file timestamps
         run_command(&[
             "-s",
             device_id,
             "pull",
+            "-a",
             &remote_obb_path,
             final_obb_target.to_str().unwrap(),
         ])
@@ -276,57 +550,90 @@ pub fn backup_package(
         false
     };
 
-    // 4. Check Root & Backup Data (/data/data)
-    // Hybrid Mode: If rooted, backup application data
-    let mut has_data = false;
-    // Simple check if we can run su
-    let su_check = run_command(&["-s", device_id, "shell", "su", "-c", "id"]);
-
-    if su_check.is_ok() {
-        let data_tar_remote = format!("/sdcard/easyadb_{}_data.tar.gz", package.name);
-
-        // Construct tar command
-        // We use -C /data/data so the archive contains just the package folder "com.example"
-        // This makes restoring easier (extraction into /data/data)
-        let tar_cmd = format!(
-            "tar -czf {} -C /data/data {}",
-            data_tar_remote, package.name
+    // 3b. External app data (/sdcard/Android/data/<pkg>), optional
+    let ext_data_dir = temp_backup_dir.join("extdata");
+    let mut external_data_size = 0;
+    let external_data_excludes = normalize_excludes(&backup_options.external_data_excludes)?;
+    let has_external_data = backup_options.include_external_data
+        && pull_external_data(
+            device_id,
+            &package.name,
+            &ext_data_dir,
+            &external_data_excludes,
         );
+    if has_external_data {
+        external_data_size =
+            get_external_data_size(device_id, &package.name, &external_data_excludes).unwrap_or(0);
+    }
 
-        let tar_res = run_command(&["-s", device_id, "shell", "su", "-c", &tar_cmd]);
-
-        if tar_res.is_ok() {
-            let local_data_path = temp_backup_dir.join("data.tar.gz");
+    // 4. Check Root & Backup Data (CE /data/user/<N> + DE /data/user_de/<N>)
+    let data_user = backup_options.user.unwrap_or(0);
+    let mut root_data = root_data::RootDataBackup::default();
+    // Non-fatal problems, appended to the result message
+    let mut warnings: Vec<String> = Vec::new();
+    // Simple check if we can run su
+    let su_check = run_command(&["-s", device_id, "shell", "su", "-c", "id"]);
 
-            // Pull the archive
-            let pull_res = run_command(&[
-                "-s",
-                device_id,
-                "pull",
-                &data_tar_remote,
-                local_data_path.to_str().unwrap(),
-            ]);
-
-            if pull_res.is_ok() && local_data_path.exists() {
-                has_data = true;
-                // Cleanup remote temp file
-                let _ = run_command(&["-s", device_id, "shell", "rm", &data_tar_remote]);
+    if backup_options.include_data && su_check.is_ok() {
+        match root_data::backup(device_id, &package.name, data_user, temp_backup_dir) {
+            Ok(result) => root_data = result,
+            Err(e) => {
+                log::warn!("Root data backup failed for {}: {}", package.name, e);
+                warnings.push(format!("app data not backed up: {}", e));
             }
         }
     }
+    let has_data = root_data.has_ce;
+
+    // 4b. Non-root fallback: `adb backup -noapk` for apps that allow backup
+    let mut adb_backup_version = None;
+    if backup_options.include_data
+        && backup_options.adb_backup_fallback
+        && !(root_data.has_ce || root_data.has_de)
+        && app_details.allow_backup != Some(false)
+    {
+        let adb_tar_path = temp_backup_dir.join("adb_data.tar");
+        match adb_backup::backup_to_tar(
+            device_id,
+            &package.name,
+            &adb_tar_path,
+            backup_options.adb_backup_password.as_deref(),
+        ) {
+            Ok(header) => adb_backup_version = Some(header.version),
+            Err(e) => log::warn!("adb backup fallback skipped for {}: {}", package.name, e),
+        }
+    }
+    let has_adb_data = adb_backup_version.is_some();
 
     // 5. Create metadata.json
-    let metadata = serde_json::json!({
-        "packageName": package.name,
-        "originalPath": package.path,
-        "backupDate": chrono::Local::now().to_rfc3339(),
-        "version": "3.0", // Hybrid Format
-        "isSplit": apk_files.len() > 1,
-        "hasObb": has_obb,
-        "hasData": has_data
-    });
+    let manifest = BackupManifest {
+        format_version: backup_manifest::FORMAT_VERSION,
+        created_by: Some(env!("CARGO_PKG_VERSION").to_string()),
+        package_name: package.name.clone(),
+        original_path: Some(package.path.clone()),
+        backup_date: Some(chrono::Local::now().to_rfc3339()),
+        is_split: apk_files.len() > 1,
+        has_obb,
+        has_data,
+        has_de_data: root_data.has_de,
+        data_user: Some(data_user).filter(|_| root_data.has_ce || root_data.has_de),
+        has_adb_data,
+        adb_backup_version,
+        has_external_data,
+        has_permissions,
+        external_data_size: Some(external_data_size),
+        external_data_excludes: external_data_excludes.clone(),
+        app: app_details,
+        source_device: Some(source_device),
+    };
     let metadata_path = temp_backup_dir.join("metadata.json");
-    fs::write(&metadata_path, metadata.to_string()).map_err(|e| e.to_string())?;
+    fs::write(&metadata_path, manifest.to_json()?).map_err(|e| e.to_string())?;
+
+    let permissions_path = temp_backup_dir.join("permissions.json");
+    if has_permissions {
+        let json = serde_json::to_string_pretty(&permission_state).map_err(|e| e.to_string())?;
+        fs::write(&permissions_path, json).map_err(|e| e.to_string())?;
+    }
 
     // 6. Create ZIP file (.easybckp)
     let file = File::create(&dest_path).map_err(|e| e.to_string())?;
@@ -354,42 +661,56 @@ pub fn backup_package(
         }
     }
 
-    // Add OBBs (if any)
-    if has_obb {
-        let obb_final_path = temp_backup_dir.join("obb");
-        if obb_final_path.exists() {
-            let obb_entries = fs::read_dir(&obb_final_path).map_err(|e| e.to_string())?;
-            for entry in obb_entries {
-                let entry = entry.map_err(|e| e.to_string())?;
-                let path = entry.path();
-                if path.is_file() {
-                    let name = path.file_name().unwrap().to_str().unwrap();
-                    let zip_path = format!("obb/{}", name);
-                    zip.start_file(zip_path, options)
-                        .map_err(|e| e.to_string())?;
-                    let mut f = File::open(&path).map_err(|e| e.to_string())?;
-                    // Large file handling? Reading whole file to memory is risky for 2GB OBB.
-                    // Ideally use stream copy.
-                    // For now, let's stick to simple impl but warn user about memory.
-                    // Actually, zip::write usually requires Write trait.
-                    // Better verify buffer size or use copy.
-                    std::io::copy(&mut f, &mut zip).map_err(|e| e.to_string())?;
-                }
-            }
-        }
+    // Add OBBs (if any) - recursive, nested OBB folders and mtimes are kept
+    let obb_final_path = temp_backup_dir.join("obb");
+    if has_obb && obb_final_path.exists() {
+        archive::add_directory(&mut zip, &obb_final_path, "obb", options, &[])?;
+    }
+
+    // Add External Data (if any)
+    if has_external_data && ext_data_dir.exists() {
+        archive::add_directory(
+            &mut zip,
+            &ext_data_dir,
+            "extdata",
+            options,
+            &external_data_excludes,
+        )?;
     }
 
-    // Add Data Archive (if any)
-    if has_data {
-        let data_path = temp_backup_dir.join("data.tar.gz");
+    // Add Data Archives (if any): CE data.tar.gz and DE data_de.tar.gz
+    for name in [root_data::CE_ARCHIVE, root_data::DE_ARCHIVE] {
+        let data_path = temp_backup_dir.join(name);
         if data_path.exists() {
-            zip.start_file("data.tar.gz", options)
+            let data_size = fs::metadata(&data_path).map(|m| m.len()).unwrap_or(0);
+            zip.start_file(name, options.large_file(data_size >= u32::MAX as u64))
                 .map_err(|e| e.to_string())?;
             let mut f = File::open(&data_path).map_err(|e| e.to_string())?;
             std::io::copy(&mut f, &mut zip).map_err(|e| e.to_string())?;
         }
     }
 
+    // Add adb backup data (non-root fallback)
+    if has_adb_data {
+        let adb_tar_path = temp_backup_dir.join("adb_data.tar");
+        let tar_size = fs::metadata(&adb_tar_path).map(|m| m.len()).unwrap_or(0);
+        zip.start_file(
+            "adb_data.tar",
+            options.large_file(tar_size >= u32::MAX as u64),
+        )
+        .map_err(|e| e.to_string())?;
+        let mut f = File::open(&adb_tar_path).map_err(|e| e.to_string())?;
+        std::io::copy(&mut f, &mut zip).map_err(|e| e.to_string())?;
+    }
+
+    // Add runtime permissions / app-ops
+    if has_permissions {
+        zip.start_file("permissions.json", options)
+            .map_err(|e| e.to_string())?;
+        let mut f = File::open(&permissions_path).map_err(|e| e.to_string())?;
+        std::io::copy(&mut f, &mut zip).map_err(|e| e.to_string())?;
+    }
+
     // Add metadata
     zip.start_file("metadata.json", options)
         .map_err(|e| e.to_string())?;
@@ -398,10 +719,15 @@ pub fn backup_package(
 
     zip.finish().map_err(|e| e.to_string())?;
 
-    // 6. Cleanup temp
-    let _ = fs::remove_dir_all(&temp_backup_dir);
-
-    Ok(format!("Backup created at {:?}", dest_path))
+    if warnings.is_empty() {
+        Ok(format!("Backup created at {:?}", dest_path))
+    } else {
+        Ok(format!(
+            "Backup created at {:?} (warning: {})",
+            dest_path,
+            warnings.join("; ")
+        ))
+    }
 }
 
 pub fn get_package_size(device_id: &str, path: &str) -> Result<u64, String> {
@@ -435,28 +761,155 @@ pub fn get_package_size(device_id: &str, path: &str) -> Result<u64, String> {
     Err("Could not determine file size".to_string())
 }
 
+/// Options for `adb install` during restore.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct RestoreOptions {
+    /// `-d`: allow installing an older version over a newer one
+    pub allow_downgrade: bool,
+    /// `-g`: grant all runtime permissions
+    pub grant_permissions: bool,
+    /// `--user N`: install for a specific user
+    pub user: Option<u32>,
+    /// `-i`: installer package name to record (e.g. com.android.vending)
+    pub installer_package: Option<String>,
+    /// `-t`: allow test-only APKs
+    pub allow_test: bool,
+    /// `--bypass-low-target-sdk-block` (Android 14+)
+    pub bypass_low_target_sdk: bool,
+    /// Don't touch the app if the installed version is newer than the backup
+    pub skip_if_newer_installed: bool,
+    /// Install every split instead of only those matching the device ABI/density/locale
+    pub install_all_splits: bool,
+    /// Which parts of the backup to restore
+    pub components: RestoreComponents,
+}
+
+/// Backup components selectable for partial restore.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RestoreComponents {
+    pub apk: bool,
+    pub obb: bool,
+    pub data: bool,
+    pub external_data: bool,
+    /// Re-apply granted runtime permissions and app-ops
+    pub permissions: bool,
+}
+
+impl Default for RestoreComponents {
+    fn default() -> Self {
+        RestoreComponents {
+            apk: true,
+            obb: true,
+            data: true,
+            external_data: true,
+            permissions: true,
+        }
+    }
+}
+
+/// Result of a restore: which components were applied or skipped.
+#[derive(Serialize, Debug, Clone)]
+pub struct RestoreReport {
+    pub package_name: String,
+    pub message: String,
+    pub applied: Vec<String>,
+    pub skipped: Vec<String>,
+    /// Permissions / app-ops that couldn't be re-applied on this device
+    pub permission_failures: Vec<String>,
+}
+
+impl RestoreOptions {
+    /// Builds the extra flags passed after `install -r` / `install-multiple -r`.
+    pub fn install_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.allow_downgrade {
+            args.push("-d".to_string());
+        }
+        if self.grant_permissions {
+            args.push("-g".to_string());
+        }
+        if self.allow_test {
+            args.push("-t".to_string());
+        }
+        if let Some(user) = self.user {
+            args.push("--user".to_string());
+            args.push(user.to_string());
+        }
+        if let Some(installer) = self.installer_package.as_ref().filter(|i| !i.is_empty()) {
+            args.push("-i".to_string());
+            args.push(installer.clone());
+        }
+        if self.bypass_low_target_sdk {
+            args.push("--bypass-low-target-sdk-block".to_string());
+        }
+        args
+    }
+}
+
+/// Installs a single APK or a split APK set with `install` / `install-multiple`.
+pub fn install_apks(
+    device_id: &str,
+    apk_paths: &[PathBuf],
+    options: &RestoreOptions,
+) -> Result<String, String> {
+    let subcommand = if apk_paths.len() == 1 {
+        "install"
+    } else {
+        "install-multiple"
+    };
+
+    let extra_args = options.install_args();
+    let paths_str: Vec<String> = apk_paths
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+
+    let mut args = vec!["-s", device_id, subcommand, "-r"];
+    args.extend(extra_args.iter().map(|a| a.as_str()));
+    args.extend(paths_str.iter().map(|p| p.as_str()));
+
+    run_command(&args)
+}
+
 /// Restores an application from a backup (.easybckp) file.
 ///
 /// This function performs a comprehensive restore process:
 /// 1. Extracts the backup archive to a temporary directory.
-/// 2. Installs the APK (handles both split APKs and legacy single APKs).
+/// 2. Installs the APK (handles both split APKs and legacy single APKs). Only the
+///    config splits matching the device's ABI, density and locales are installed.
 /// 3. Restores OBB files if present.
 /// 4. Restores application data (requires root access).
+/// 5. Re-applies runtime permissions and app-ops.
+///
+/// Each step can be turned off through `options.components` (e.g. data-only
+/// rollback of an app that is already installed).
+///
+/// The installed app (APKs, plus root data when data is restored) is
+/// snapshotted first. If a step fails the snapshot is put back and the error
+/// says what was rolled back.
 ///
 /// # Arguments
 ///
 /// * `device_id` - The serial number of the target Android device.
 /// * `backup_path` - The absolute path to the `.easybckp` file.
+/// * `options` - Extra `adb install` flags (downgrade, grant permissions, user...).
 ///
 /// # Returns
 ///
-/// * `Result<String, String>` - Success message or error description.
+/// * `Result<RestoreReport, String>` - Applied/skipped components or error description.
 ///
 /// # Security
 ///
-/// * Data restore requires root access (`su`).
+/// * Data restore requires root access (`su`), or confirmation on the device
+///   for backups taken with `adb backup`.
 /// * Automatically fixes permissions (`chown`) and SELinux context (`restorecon`) after data restore.
-pub fn restore_package(device_id: &str, backup_path: PathBuf) -> Result<String, String> {
+pub fn restore_package(
+    device_id: &str,
+    backup_path: PathBuf,
+    options: &RestoreOptions,
+) -> Result<RestoreReport, String> {
     // 1. Prepare temp dir for extraction
     let file_name = backup_path
         .file_stem()
@@ -475,156 +928,250 @@ pub fn restore_package(device_id: &str, backup_path: PathBuf) -> Result<String,
     let mut archive =
         zip::ZipArchive::new(file).map_err(|e| format!("Failed to read zip: {}", e))?;
 
-    archive
-        .extract(&temp_restore_dir)
+    // Reject backups from newer app versions before anything is extracted
+    let manifest = backup_manifest::read_manifest(&mut archive)?;
+
+    archive::extract_directory(&mut archive, "", &temp_restore_dir)
         .map_err(|e| format!("Failed to extract: {}", e))?;
 
-    // 3. Analyze Structure (Legacy or Universal)
+    let package_name = manifest.package_name.clone();
+    let backup_version_code = manifest.app.version_code;
+
+    let mut report = RestoreReport {
+        package_name: package_name.clone(),
+        message: String::new(),
+        applied: Vec::new(),
+        skipped: Vec::new(),
+        permission_failures: Vec::new(),
+    };
+    let components = &options.components;
+
+    // Skip if a newer version is already installed (optional)
+    if options.skip_if_newer_installed && components.apk && !package_name.is_empty() {
+        if let Some(backup_code) = backup_version_code {
+            let installed = package_info::query_package_details(device_id, &package_name);
+            if installed
+                .version_code
+                .is_some_and(|code| code > backup_code)
+            {
+                let _ = fs::remove_dir_all(&temp_restore_dir);
+                report.skipped.push("apk".to_string());
+                report.message = format!(
+                    "Skipped: a newer version of {} is already installed",
+                    package_name
+                );
+                return Ok(report);
+            }
+        }
+    }
+
+    // OBB/Data-only restore needs the app to be on the device already
+    if !components.apk {
+        if package_name.is_empty() {
+            return Err("Partial restore requires a backup with metadata".to_string());
+        }
+        let installed = run_command(&["-s", device_id, "shell", "pm", "path", &package_name])
+            .map(|out| out.contains("package:"))
+            .unwrap_or(false);
+        if !installed {
+            return Err(format!(
+                "{} is not installed. Restore the APK as well.",
+                package_name
+            ));
+        }
+        report.skipped.push("apk".to_string());
+    }
+
+    // Snapshot what is installed now so a failed restore can be undone
+    let snapshot = if package_name.is_empty() {
+        None
+    } else {
+        let with_data =
+            components.data && (manifest.has_data || manifest.has_de_data || manifest.has_adb_data);
+        Some(rollback::take(
+            device_id,
+            &package_name,
+            options.user.or(manifest.data_user).unwrap_or(0),
+            components.apk,
+            with_data,
+        ))
+    };
+
+    if let Err(e) = apply_restore_steps(
+        device_id,
+        &temp_restore_dir,
+        &manifest,
+        options,
+        &mut report,
+    ) {
+        let _ = fs::remove_dir_all(&temp_restore_dir);
+        return Err(match snapshot {
+            Some(snapshot) => {
+                let summary = snapshot.rollback(device_id, options);
+                snapshot.discard();
+                format!("{}. {}", e, summary)
+            }
+            None => e,
+        });
+    }
+    if let Some(snapshot) = snapshot {
+        snapshot.discard();
+    }
+
+    report.message = if report.applied.is_empty() {
+        "Nothing to restore".to_string()
+    } else {
+        format!("Restored: {}", report.applied.join(", "))
+    };
+    Ok(report)
+}
+
+/// Steps 1-4 of `restore_package`. Any error aborts the restore and triggers a rollback.
+fn apply_restore_steps(
+    device_id: &str,
+    temp_restore_dir: &Path,
+    manifest: &BackupManifest,
+    options: &RestoreOptions,
+    report: &mut RestoreReport,
+) -> Result<(), String> {
+    // Analyze Structure (Legacy or Universal)
     let apks_dir = temp_restore_dir.join("apks");
     let legacy_apk_path = temp_restore_dir.join("base.apk");
     let obb_dir = temp_restore_dir.join("obb");
 
-    // Read metadata if exists to get package name for OBB and Root Data flag
-    let metadata_path = temp_restore_dir.join("metadata.json");
-    let mut package_name = String::new();
-    let mut has_data_archive = false;
+    let package_name = manifest.package_name.clone();
+    let has_data_archive = manifest.has_data;
+    let adb_backup_version = manifest.adb_backup_version;
+    let components = &options.components;
 
-    if metadata_path.exists() {
-        if let Ok(content) = fs::read_to_string(&metadata_path) {
-            if let Ok(json) = serde_json::from_str::<serde_json::Value>(&content) {
-                if let Some(name) = json["packageName"].as_str() {
-                    package_name = name.to_string();
-                }
-                if let Some(data) = json["hasData"].as_bool() {
-                    has_data_archive = data;
+    // Step 1: Install APKs
+    if components.apk {
+        let install_output = if apks_dir.exists() {
+            // Universal Format (Folder of APKs)
+            let entries = fs::read_dir(&apks_dir).map_err(|e| e.to_string())?;
+            let mut apk_paths = Vec::new();
+            for entry in entries {
+                let entry = entry.map_err(|e| e.to_string())?;
+                let path = entry.path();
+                if path.extension().map_or(false, |e| e == "apk") {
+                    apk_paths.push(path);
                 }
             }
-        }
-    }
 
-    // Step 1: Install APKs
-    let install_output = if apks_dir.exists() {
-        // Universal Format (Folder of APKs)
-        let entries = fs::read_dir(&apks_dir).map_err(|e| e.to_string())?;
-        let mut apk_paths = Vec::new();
-        for entry in entries {
-            let entry = entry.map_err(|e| e.to_string())?;
-            let path = entry.path();
-            if path.extension().map_or(false, |e| e == "apk") {
-                apk_paths.push(path);
+            if apk_paths.is_empty() {
+                return Err("Invalid backup: No APKs found in apks/ directory".to_string());
             }
-        }
 
-        if apk_paths.is_empty() {
-            return Err("Invalid backup: No APKs found in apks/ directory".to_string());
-        }
+            if !options.install_all_splits {
+                let selection = splits::select_for_device(device_id, &apk_paths)?;
+                if !selection.excluded.is_empty() {
+                    report
+                        .skipped
+                        .push(format!("splits: {}", selection.excluded.join(", ")));
+                }
+                apk_paths = selection.selected;
+            }
 
-        if apk_paths.len() == 1 {
-            // Single APK install
-            run_command(&[
-                "-s",
-                device_id,
-                "install",
-                "-r",
-                apk_paths[0].to_str().unwrap(),
-            ])?
+            install_apks(device_id, &apk_paths, options)?
+        } else if legacy_apk_path.exists() {
+            // Legacy Format (Root base.apk)
+            install_apks(device_id, &[legacy_apk_path], options)?
         } else {
-            // Multiple APK install (Split APKs)
-            let args = vec!["-s", device_id, "install-multiple", "-r"];
-            let paths_str: Vec<String> = apk_paths
-                .iter()
-                .map(|p| p.to_str().unwrap().to_string())
-                .collect();
-            // We need string references that live long enough
-            // run_command expects &[&str].
-            // We need to build the arg list carefully.
-            let mut final_args: Vec<&str> = args.clone();
-            for p in &paths_str {
-                final_args.push(p);
-            }
-            run_command(&final_args)?
-        }
-    } else if legacy_apk_path.exists() {
-        // Legacy Format (Root base.apk)
-        run_command(&[
-            "-s",
-            device_id,
-            "install",
-            "-r",
-            legacy_apk_path.to_str().unwrap(),
-        ])?
-    } else {
-        return Err("Invalid backup structure: No APKs found".to_string());
-    };
+            return Err("Invalid backup structure: No APKs found".to_string());
+        };
 
-    if !install_output.contains("Success") {
-        return Err(format!("Install Failed: {}", install_output));
+        if !install_output.contains("Success") {
+            return Err(format!("Install Failed: {}", install_output));
+        }
+        report.applied.push("apk".to_string());
     }
 
     // Step 2: Restore OBB (if exists)
-    if obb_dir.exists() && !package_name.is_empty() {
-        // Target: /sdcard/Android/obb/<package_name>/
-        let target_obb = format!("/sdcard/Android/obb/{}/", package_name);
-
-        // Ensure directory exists
-        let _ = run_command(&["-s", device_id, "shell", "mkdir", "-p", &target_obb]);
+    if !components.obb && obb_dir.exists() {
+        report.skipped.push("obb".to_string());
+    }
+    if components.obb && obb_dir.exists() && !package_name.is_empty() {
+        // Target: /sdcard/Android/obb/<package_name>/ (nested folders included)
+        let target_obb = format!("/sdcard/Android/obb/{}", package_name);
+        archive::push_directory(device_id, &obb_dir, &target_obb)?;
+        report.applied.push("obb".to_string());
+    }
 
-        // Push files
-        let entries = fs::read_dir(&obb_dir).map_err(|e| e.to_string())?;
-        for entry in entries {
-            let entry = entry.map_err(|e| e.to_string())?;
-            let path = entry.path();
-            if path.is_file() {
-                let file_name = path.file_name().unwrap().to_str().unwrap();
-                let remote_file_path = format!("{}{}", target_obb, file_name);
-                run_command(&[
-                    "-s",
-                    device_id,
-                    "push",
-                    path.to_str().unwrap(),
-                    &remote_file_path,
-                ])?;
-            }
+    // Step 2b: Restore External Data (/sdcard/Android/data/<pkg>)
+    let ext_data_dir = temp_restore_dir.join("extdata");
+    if !components.external_data && ext_data_dir.exists() {
+        report.skipped.push("external_data".to_string());
+    }
+    if components.external_data && ext_data_dir.exists() && !package_name.is_empty() {
+        if push_external_data(device_id, &package_name, &ext_data_dir) {
+            report.applied.push("external_data".to_string());
+        } else {
+            report
+                .skipped
+                .push("external_data (no access to Android/data)".to_string());
         }
     }
 
-    // Step 3: Restore Data (Root Only)
-    let data_tar_path = temp_restore_dir.join("data.tar.gz");
-    if has_data_archive && data_tar_path.exists() && !package_name.is_empty() {
+    // Step 3: Restore Data (Root Only) - CE and DE dirs of the target user
+    let ce_archive = temp_restore_dir.join(root_data::CE_ARCHIVE);
+    let de_archive = temp_restore_dir.join(root_data::DE_ARCHIVE);
+    let ce_archive = Some(ce_archive).filter(|p| has_data_archive && p.exists());
+    let de_archive = Some(de_archive).filter(|p| manifest.has_de_data && p.exists());
+    let has_data = (ce_archive.is_some() || de_archive.is_some()) && !package_name.is_empty();
+    if has_data && !components.data {
+        report.skipped.push("data".to_string());
+    }
+    if has_data && components.data {
         // Check root access
-        if run_command(&["-s", device_id, "shell", "su", "-c", "id"]).is_ok() {
-            let remote_tar_path = "/sdcard/easyadb_restore_data.tar.gz";
-
-            run_command(&[
-                "-s",
+        if run_command(&["-s", device_id, "shell", "su", "-c", "id"]).is_err() {
+            report.skipped.push("data (root required)".to_string());
+        } else {
+            // Restore into the requested user, or the one the backup was taken from
+            let user = options.user.or(manifest.data_user).unwrap_or(0);
+            root_data::restore(
                 device_id,
-                "push",
-                data_tar_path.to_str().unwrap(),
-                remote_tar_path,
-            ])?;
-
-            // P1 #4: tar extract - backup'ta klasör yapısı {package_name}/... şeklinde
-            // /data/data/{package_name} hedefine açıyoruz
-            let extract_cmd = format!("tar -xzf {} -C /data/data", remote_tar_path);
-            run_command(&["-s", device_id, "shell", "su", "-c", &extract_cmd])?;
-
-            // P1 #5: Permission ve SELinux context düzeltmesi
-            // Ownership'i uygulamanın UID'sine ayarla
-            let chown_cmd = format!(
-                "chown -R $(stat -c '%u:%g' /data/data/{0}/.) /data/data/{0}",
-                package_name
-            );
-            let _ = run_command(&["-s", device_id, "shell", "su", "-c", &chown_cmd]);
+                &package_name,
+                user,
+                ce_archive.as_deref(),
+                de_archive.as_deref(),
+            )?;
+            report.applied.push("data".to_string());
+        }
+    }
 
-            // SELinux context'i düzelt (Android 5.0+ için gerekli)
-            let restorecon_cmd = format!("restorecon -R /data/data/{}", package_name);
-            let _ = run_command(&["-s", device_id, "shell", "su", "-c", &restorecon_cmd]);
+    // Step 3b: Restore Data via `adb restore` (non-root backups, or root data unavailable)
+    let adb_tar_path = temp_restore_dir.join("adb_data.tar");
+    let data_restored = report.applied.iter().any(|c| c == "data");
+    if adb_tar_path.exists() && !package_name.is_empty() && !data_restored {
+        if !components.data {
+            if !has_data {
+                report.skipped.push("data".to_string());
+            }
+        } else {
+            // Android accepts streams up to its own format version; 5 is Android 7+
+            let version = adb_backup_version.unwrap_or(5);
+            adb_backup::restore_from_tar(device_id, &adb_tar_path, version)?;
+            report.applied.push("data (adb restore)".to_string());
+        }
+    }
 
-            // Cleanup remote temp file
-            run_command(&["-s", device_id, "shell", "rm", remote_tar_path])?;
+    // Step 4: Re-apply runtime permissions & app-ops
+    let permissions_path = temp_restore_dir.join("permissions.json");
+    if permissions_path.exists() && !package_name.is_empty() {
+        if components.permissions {
+            let state: permissions::PermissionState = fs::read_to_string(&permissions_path)
+                .ok()
+                .and_then(|c| serde_json::from_str(&c).ok())
+                .unwrap_or_default();
+            report.permission_failures =
+                permissions::apply(device_id, &package_name, &state, options.user);
+            report.applied.push("permissions".to_string());
+        } else {
+            report.skipped.push("permissions".to_string());
         }
     }
-    Ok("Restore completed".to_string())
+
+    Ok(())
 }
 
 pub fn is_device_rooted(device_id: &str) -> bool {
@@ -709,17 +1256,58 @@ where
     Ok("Sideload Complete".to_string())
 }
 
-/// Executes a Fastboot command.
-pub fn run_fastboot_command(args: &[&str]) -> Result<String, String> {
+/// Starts an adb process with piped stdout for streaming reads (e.g. `exec-out`).
+pub fn spawn_command(args: &[&str]) -> Result<Child, String> {
+    Command::new(get_adb_path())
+        .args(args)
+        .creation_flags(CREATE_NO_WINDOW)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to execute process: {}", e))
+}
+
+fn fastboot_program() -> String {
     // Try to find fastboot in the temp dir first, otherwise assume global PATH
     let temp_fastboot = env::temp_dir().join("easyadb_tools").join("fastboot.exe");
-    let program = if temp_fastboot.exists() {
+    if temp_fastboot.exists() {
         temp_fastboot.to_string_lossy().to_string()
     } else {
         "fastboot".to_string()
-    };
+    }
+}
+
+/// Reads a bootloader variable (`fastboot getvar <name>`). fastboot prints
+/// the value as "name: value" on stderr.
+pub fn fastboot_getvar(serial: Option<&str>, name: &str) -> Result<String, String> {
+    let mut args = Vec::new();
+    if let Some(serial) = serial {
+        args.extend(["-s", serial]);
+    }
+    args.extend(["getvar", name]);
+
+    let output = Command::new(fastboot_program())
+        .args(&args)
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("Failed to execute fastboot: {}", e))?;
 
-    let output = Command::new(program)
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let prefix = format!("{}:", name);
+    text.lines()
+        .find_map(|l| l.trim().strip_prefix(&prefix))
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| format!("Could not read {} from fastboot", name))
+}
+
+/// Executes a Fastboot command.
+pub fn run_fastboot_command(args: &[&str]) -> Result<String, String> {
+    let output = Command::new(fastboot_program())
         .args(args)
         .creation_flags(CREATE_NO_WINDOW)
         .output()
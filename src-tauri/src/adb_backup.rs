@@ -0,0 +1,362 @@
+//! Non-root data backup through Android's `adb backup` (.ab) format.
+//!
+//! `.ab` layout:
+//!   ANDROID BACKUP\n <version>\n <compressed 0|1>\n <none|AES-256>\n
+//!   [AES-256: user salt, checksum salt, rounds, user IV, master key blob (hex lines)]
+//!   payload = [AES-256-CBC] ( [zlib] ( tar ) )
+//!
+//! Backups are stored as a plain tar inside the `.easybckp`; for restore the tar
+//! is wrapped back into an unencrypted, compressed `.ab` stream for `adb restore`.
+
+use aes::Aes256;
+use cbc::cipher::block_padding::Pkcs7;
+use cbc::cipher::generic_array::GenericArray;
+use cbc::cipher::{BlockDecryptMut, KeyIvInit};
+use sha1::Sha1;
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+
+use crate::adb::run_command;
+
+type Aes256CbcDec = cbc::Decryptor<Aes256>;
+
+const AB_MAGIC: &str = "ANDROID BACKUP";
+const AES_BLOCK: usize = 16;
+const TAR_BLOCK: usize = 512;
+
+#[derive(Debug, Clone)]
+pub struct AbHeader {
+    pub version: u32,
+    pub compressed: bool,
+    pub encryption: String,
+}
+
+fn read_header_line<R: BufRead>(reader: &mut R) -> Result<String, String> {
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .map_err(|e| format!("Failed to read backup header: {}", e))?;
+    if line.is_empty() {
+        return Err("Unexpected end of backup header".to_string());
+    }
+    Ok(line.trim_end_matches(['\n', '\r']).to_string())
+}
+
+fn hex_decode(hex: &str) -> Result<Vec<u8>, String> {
+    if hex.len() % 2 != 0 {
+        return Err("Invalid hex in backup header".to_string());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| "Invalid hex in backup header".to_string())
+        })
+        .collect()
+}
+
+/// Android converts the master key to Java chars (sign-extended bytes) before
+/// running PBKDF2 over their UTF-8 encoding. Version 1 used the raw bytes.
+fn java_char_bytes(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len() * 3);
+    for &b in bytes {
+        let c = b as i8 as i16 as u16 as u32;
+        let ch = char::from_u32(c).unwrap_or('\u{FFFD}');
+        let mut buf = [0u8; 4];
+        out.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+    }
+    out
+}
+
+fn pbkdf2_sha1(password: &[u8], salt: &[u8], rounds: u32) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha1>(password, salt, rounds, &mut key);
+    key
+}
+
+/// Streaming AES-256-CBC decryption with PKCS#7 padding removal at EOF.
+struct CbcDecryptReader<R: Read> {
+    inner: R,
+    decryptor: Aes256CbcDec,
+    ready: Vec<u8>,
+    pos: usize,
+    /// Last decrypted block, held back until we know whether it carries padding
+    held: Option<[u8; AES_BLOCK]>,
+    pending: Vec<u8>,
+    finished: bool,
+}
+
+impl<R: Read> CbcDecryptReader<R> {
+    fn new(inner: R, key: &[u8], iv: &[u8]) -> Result<Self, String> {
+        let decryptor = Aes256CbcDec::new_from_slices(key, iv)
+            .map_err(|_| "Invalid backup encryption parameters".to_string())?;
+        Ok(CbcDecryptReader {
+            inner,
+            decryptor,
+            ready: Vec::new(),
+            pos: 0,
+            held: None,
+            pending: Vec::new(),
+            finished: false,
+        })
+    }
+
+    fn fill(&mut self) -> std::io::Result<()> {
+        self.ready.clear();
+        self.pos = 0;
+
+        let mut chunk = vec![0u8; 64 * 1024];
+        while self.ready.is_empty() && !self.finished {
+            let n = self.inner.read(&mut chunk)?;
+            if n == 0 {
+                self.finished = true;
+                if !self.pending.is_empty() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "Encrypted payload is not block aligned",
+                    ));
+                }
+                // Strip PKCS#7 padding from the final block
+                if let Some(last) = self.held.take() {
+                    let pad = last[AES_BLOCK - 1] as usize;
+                    if pad == 0
+                        || pad > AES_BLOCK
+                        || !last[AES_BLOCK - pad..].iter().all(|&b| b as usize == pad)
+                    {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            "Invalid padding (wrong password?)",
+                        ));
+                    }
+                    self.ready.extend_from_slice(&last[..AES_BLOCK - pad]);
+                }
+                break;
+            }
+
+            self.pending.extend_from_slice(&chunk[..n]);
+            let whole = self.pending.len() / AES_BLOCK * AES_BLOCK;
+            let blocks: Vec<u8> = self.pending.drain(..whole).collect();
+
+            for block in blocks.chunks(AES_BLOCK) {
+                let mut buf = [0u8; AES_BLOCK];
+                buf.copy_from_slice(block);
+                self.decryptor
+                    .decrypt_block_mut(GenericArray::from_mut_slice(&mut buf));
+                if let Some(prev) = self.held.replace(buf) {
+                    self.ready.extend_from_slice(&prev);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for CbcDecryptReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        if self.pos >= self.ready.len() {
+            self.fill()?;
+        }
+        let available = &self.ready[self.pos..];
+        let n = available.len().min(out.len());
+        out[..n].copy_from_slice(&available[..n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Reads the AES-256 header lines and unwraps the master key and IV.
+fn unwrap_master_key<R: BufRead>(
+    reader: &mut R,
+    version: u32,
+    password: &str,
+) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let user_salt = hex_decode(&read_header_line(reader)?)?;
+    let checksum_salt = hex_decode(&read_header_line(reader)?)?;
+    let rounds: u32 = read_header_line(reader)?
+        .parse()
+        .map_err(|_| "Invalid PBKDF2 rounds in backup header".to_string())?;
+    let user_iv = hex_decode(&read_header_line(reader)?)?;
+    let mut blob = hex_decode(&read_header_line(reader)?)?;
+
+    let user_key = pbkdf2_sha1(password.as_bytes(), &user_salt, rounds);
+    let plain = Aes256CbcDec::new_from_slices(&user_key, &user_iv)
+        .map_err(|_| "Invalid backup encryption parameters".to_string())?
+        .decrypt_padded_mut::<Pkcs7>(&mut blob)
+        .map_err(|_| "Wrong backup password".to_string())?;
+
+    // Blob: [len][master IV][len][master key][len][checksum]
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    for _ in 0..3 {
+        let len = *plain.get(pos).ok_or("Corrupt master key blob")? as usize;
+        let field = plain
+            .get(pos + 1..pos + 1 + len)
+            .ok_or("Corrupt master key blob")?;
+        fields.push(field.to_vec());
+        pos += 1 + len;
+    }
+    let (master_iv, master_key, checksum) = (&fields[0], &fields[1], &fields[2]);
+
+    let checksum_input = if version >= 2 {
+        java_char_bytes(master_key)
+    } else {
+        master_key.clone()
+    };
+    if pbkdf2_sha1(&checksum_input, &checksum_salt, rounds).as_slice() != checksum.as_slice() {
+        return Err("Wrong backup password".to_string());
+    }
+
+    Ok((master_key.clone(), master_iv.clone()))
+}
+
+/// Decodes an `.ab` file into a plain tar. Returns the parsed header.
+pub fn ab_to_tar(
+    ab_path: &Path,
+    tar_path: &Path,
+    password: Option<&str>,
+) -> Result<AbHeader, String> {
+    let file = File::open(ab_path).map_err(|e| format!("Failed to open .ab: {}", e))?;
+    let mut reader = BufReader::new(file);
+
+    if read_header_line(&mut reader)? != AB_MAGIC {
+        return Err("Not an Android backup file".to_string());
+    }
+    let version: u32 = read_header_line(&mut reader)?
+        .parse()
+        .map_err(|_| "Invalid backup version".to_string())?;
+    let compressed = read_header_line(&mut reader)? == "1";
+    let encryption = read_header_line(&mut reader)?;
+
+    let payload: Box<dyn Read> = match encryption.as_str() {
+        "none" => Box::new(reader),
+        "AES-256" => {
+            let password = password
+                .filter(|p| !p.is_empty())
+                .ok_or("Backup is encrypted. A backup password is required.")?;
+            let (key, iv) = unwrap_master_key(&mut reader, version, password)?;
+            Box::new(CbcDecryptReader::new(reader, &key, &iv)?)
+        }
+        other => return Err(format!("Unsupported backup encryption: {}", other)),
+    };
+
+    let mut payload: Box<dyn Read> = if compressed {
+        Box::new(flate2::read::ZlibDecoder::new(payload))
+    } else {
+        payload
+    };
+
+    let mut out = File::create(tar_path).map_err(|e| e.to_string())?;
+    std::io::copy(&mut payload, &mut out)
+        .map_err(|e| format!("Failed to decode backup payload: {}", e))?;
+
+    Ok(AbHeader {
+        version,
+        compressed,
+        encryption,
+    })
+}
+
+/// Wraps a tar into an unencrypted, zlib-compressed `.ab` stream.
+pub fn tar_to_ab(tar_path: &Path, ab_path: &Path, version: u32) -> Result<(), String> {
+    let mut out = File::create(ab_path).map_err(|e| e.to_string())?;
+    write!(out, "{}\n{}\n1\nnone\n", AB_MAGIC, version).map_err(|e| e.to_string())?;
+
+    let mut encoder = flate2::write::ZlibEncoder::new(out, flate2::Compression::default());
+    let mut tar = File::open(tar_path).map_err(|e| e.to_string())?;
+    std::io::copy(&mut tar, &mut encoder).map_err(|e| e.to_string())?;
+    encoder.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Parses an octal tar header field ("0000644\0").
+fn parse_octal(field: &[u8]) -> u64 {
+    field
+        .iter()
+        .take_while(|&&b| b != 0 && b != b' ')
+        .skip_while(|&&b| b == b' ')
+        .fold(0, |acc, &b| acc * 8 + (b.wrapping_sub(b'0') as u64 & 7))
+}
+
+/// Lists entry names in a tar file (ustar headers only, which is what Android writes).
+pub fn tar_entry_names(tar_path: &Path) -> Result<Vec<String>, String> {
+    let mut file = BufReader::new(File::open(tar_path).map_err(|e| e.to_string())?);
+    let mut names = Vec::new();
+    let mut header = [0u8; TAR_BLOCK];
+
+    loop {
+        if file.read_exact(&mut header).is_err() || header.iter().all(|&b| b == 0) {
+            break;
+        }
+        let name_end = header[..100].iter().position(|&b| b == 0).unwrap_or(100);
+        let mut name = String::from_utf8_lossy(&header[..name_end]).to_string();
+        // ustar prefix field
+        if &header[257..262] == b"ustar" {
+            let prefix_end = header[345..500].iter().position(|&b| b == 0).unwrap_or(155);
+            if prefix_end > 0 {
+                let prefix = String::from_utf8_lossy(&header[345..345 + prefix_end]);
+                name = format!("{}/{}", prefix, name);
+            }
+        }
+        names.push(name);
+
+        let size = parse_octal(&header[124..136]);
+        let padded = size.div_ceil(TAR_BLOCK as u64) * TAR_BLOCK as u64;
+        std::io::copy(&mut (&mut file).take(padded), &mut std::io::sink())
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(names)
+}
+
+/// Runs `adb backup -noapk <pkg>` and stores the app's data as a plain tar.
+/// The user has to confirm the backup on the device screen.
+pub fn backup_to_tar(
+    device_id: &str,
+    package_name: &str,
+    tar_path: &Path,
+    password: Option<&str>,
+) -> Result<AbHeader, String> {
+    // Next to the tar, i.e. inside the caller's own work folder
+    let ab_path = tar_path.with_extension("ab");
+    if let Some(parent) = ab_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let ab_str = ab_path.to_string_lossy().to_string();
+
+    let result = run_command(&[
+        "-s",
+        device_id,
+        "backup",
+        "-f",
+        &ab_str,
+        "-noapk",
+        package_name,
+    ])
+    .and_then(|_| ab_to_tar(&ab_path, tar_path, password));
+    let _ = fs::remove_file(&ab_path);
+    let header = result?;
+
+    // An unconfirmed backup or an app with allowBackup=false yields an empty stream
+    let prefix = format!("apps/{}/", package_name);
+    let names = tar_entry_names(tar_path)?;
+    if !names.iter().any(|n| n.starts_with(&prefix)) {
+        let _ = fs::remove_file(tar_path);
+        return Err("adb backup produced no app data (not confirmed on device?)".to_string());
+    }
+
+    Ok(header)
+}
+
+/// Restores app data from a tar produced by `backup_to_tar` via `adb restore`.
+/// The user has to confirm the restore on the device screen.
+pub fn restore_from_tar(device_id: &str, tar_path: &Path, version: u32) -> Result<(), String> {
+    let ab_path = tar_path.with_extension("ab");
+    tar_to_ab(tar_path, &ab_path, version)?;
+
+    let ab_str = ab_path.to_string_lossy().to_string();
+    let result = run_command(&["-s", device_id, "restore", &ab_str]);
+    let _ = fs::remove_file(&ab_path);
+    result.map(|_| ())
+}
@@ -0,0 +1,105 @@
+//! Reinstalls a device's previous app set (e.g. after a factory reset).
+//!
+//! The profile's last known-good app set (`DeviceProfile::known_apps`, which
+//! a sync of a wiped device doesn't overwrite) is compared with the live
+//! package list; user apps that are gone are matched to their newest backup in
+//! the catalog and queued as one bulk restore job. Apps without a backup are
+//! listed so the user can install them from the store.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use tauri::{AppHandle, Manager};
+
+use crate::adb;
+use crate::backup_catalog::BackupCatalog;
+use crate::device_cache::DeviceCache;
+use crate::restore_queue::{self, BulkRestoreRequest, ConflictPolicy, PlannedRestore};
+
+/// Eksik uygulamalar ve eşleşen yedekler
+#[derive(Serialize, Debug, Clone)]
+pub struct AppSetPlan {
+    pub serial: String,
+    /// When the known app set was recorded
+    pub profile_date: String,
+    /// Missing user apps that have a backup (newest per package)
+    pub restorable: Vec<PlannedRestore>,
+    /// Missing user apps without any backup
+    pub without_backup: Vec<String>,
+    /// Bulk restore job started for `restorable`, if any
+    pub job_id: Option<String>,
+}
+
+/// Compares the cached profile with the device and matches missing apps to backups.
+pub fn plan(
+    cache: &DeviceCache,
+    catalog: &BackupCatalog,
+    device_id: &str,
+) -> Result<AppSetPlan, String> {
+    let profile = cache
+        .get_profile(device_id)
+        .ok_or_else(|| format!("No cached package list for {}", device_id))?;
+
+    let live: HashSet<String> = adb::get_packages(device_id)?
+        .into_iter()
+        .map(|p| p.name)
+        .collect();
+
+    // `known_apps` already leaves out system and debloated apps
+    let missing: Vec<String> = profile
+        .known_apps
+        .iter()
+        .filter(|name| !live.contains(*name))
+        .cloned()
+        .collect();
+
+    catalog.refresh();
+    let restorable = restore_queue::plan(
+        catalog,
+        &BulkRestoreRequest {
+            device_id: device_id.to_string(),
+            packages: missing.clone(),
+            default_policy: ConflictPolicy::SkipIfInstalled,
+            ..Default::default()
+        },
+    );
+    let without_backup = missing
+        .into_iter()
+        .filter(|name| !restorable.iter().any(|r| &r.package_name == name))
+        .collect();
+
+    Ok(AppSetPlan {
+        serial: device_id.to_string(),
+        profile_date: profile.known_apps_date.unwrap_or(profile.last_sync),
+        restorable,
+        without_backup,
+        job_id: None,
+    })
+}
+
+/// Plans and queues the restores. `dry_run` only returns the plan.
+pub fn restore_app_set(
+    app: &AppHandle,
+    device_id: &str,
+    dry_run: bool,
+) -> Result<AppSetPlan, String> {
+    let mut plan = plan(
+        &app.state::<DeviceCache>(),
+        &app.state::<BackupCatalog>(),
+        device_id,
+    )?;
+
+    if !dry_run && !plan.restorable.is_empty() {
+        let request = BulkRestoreRequest {
+            device_id: device_id.to_string(),
+            backups: plan
+                .restorable
+                .iter()
+                .map(|r| r.backup_path.clone())
+                .collect(),
+            default_policy: ConflictPolicy::SkipIfInstalled,
+            ..Default::default()
+        };
+        plan.job_id = Some(restore_queue::start(app, request)?);
+    }
+    Ok(plan)
+}
@@ -0,0 +1,220 @@
+//! Recursive directory archiving for backup components (OBB, external data, ...).
+//!
+//! Preserves the relative folder structure, file modification times and empty
+//! directories in both directions: device -> PC folder -> `.easybckp` and back.
+
+use chrono::{Datelike, Timelike};
+use std::fs::{self, File};
+use std::io::{Read, Seek, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+use zip::write::FileOptions;
+
+use crate::adb::run_command;
+
+/// Upper bound for a single extracted entry. Real OBB/data archives stay far
+/// below this; larger sizes mean a corrupt or malicious archive.
+pub const MAX_ENTRY_SIZE: u64 = 64 * 1024 * 1024 * 1024;
+
+/// Converts a filesystem timestamp to a zip (DOS) timestamp.
+/// DOS time only covers 1980-2107; anything outside falls back to the zip default.
+fn to_zip_time(time: SystemTime) -> Option<zip::DateTime> {
+    let local: chrono::DateTime<chrono::Local> = time.into();
+    zip::DateTime::from_date_and_time(
+        u16::try_from(local.year()).ok()?,
+        local.month() as u8,
+        local.day() as u8,
+        local.hour() as u8,
+        local.minute() as u8,
+        local.second() as u8,
+    )
+    .ok()
+}
+
+fn from_zip_time(time: zip::DateTime) -> Option<SystemTime> {
+    let naive = chrono::NaiveDate::from_ymd_opt(
+        time.year() as i32,
+        time.month() as u32,
+        time.day() as u32,
+    )?
+    .and_hms_opt(
+        time.hour() as u32,
+        time.minute() as u32,
+        time.second() as u32,
+    )?;
+    let local = naive.and_local_timezone(chrono::Local).earliest()?;
+    Some(local.into())
+}
+
+fn is_excluded(relative: &str, excludes: &[String]) -> bool {
+    excludes.iter().any(|ex| {
+        let ex = ex.trim_matches('/');
+        !ex.is_empty() && (relative == ex || relative.starts_with(&format!("{}/", ex)))
+    })
+}
+
+/// Recursively adds `dir` to the zip under `zip_prefix`.
+///
+/// * Every folder gets its own entry, so empty directories survive the round trip.
+/// * File entries carry the source file's modification time.
+/// * `excludes` are paths relative to `dir` (e.g. "cache" or "files/tmp").
+pub fn add_directory<W: Write + Seek>(
+    zip: &mut zip::ZipWriter<W>,
+    dir: &Path,
+    zip_prefix: &str,
+    options: FileOptions<()>,
+    excludes: &[String],
+) -> Result<(), String> {
+    let mut stack = vec![dir.to_path_buf()];
+
+    while let Some(current) = stack.pop() {
+        let mut entries: Vec<_> = fs::read_dir(&current)
+            .map_err(|e| e.to_string())?
+            .flatten()
+            .map(|e| e.path())
+            .collect();
+        // Stable archive order makes deduplication and diffs predictable
+        entries.sort();
+
+        for path in entries {
+            let relative = path
+                .strip_prefix(dir)
+                .map_err(|e| e.to_string())?
+                .to_string_lossy()
+                .replace('\\', "/");
+
+            if is_excluded(&relative, excludes) {
+                continue;
+            }
+
+            let meta = fs::metadata(&path).map_err(|e| e.to_string())?;
+            let mut entry_options = options.large_file(meta.len() >= u32::MAX as u64);
+            if let Some(time) = meta.modified().ok().and_then(to_zip_time) {
+                entry_options = entry_options.last_modified_time(time);
+            }
+
+            if meta.is_dir() {
+                zip.add_directory(format!("{}/{}/", zip_prefix, relative), entry_options)
+                    .map_err(|e| e.to_string())?;
+                stack.push(path);
+            } else if meta.is_file() {
+                zip.start_file(format!("{}/{}", zip_prefix, relative), entry_options)
+                    .map_err(|e| e.to_string())?;
+                let mut f = File::open(&path).map_err(|e| e.to_string())?;
+                std::io::copy(&mut f, zip).map_err(|e| e.to_string())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates an archive entry before anything is written and returns its relative path.
+///
+/// Rejects absolute paths, drive prefixes, `..` components, symlinks and
+/// entries larger than [`MAX_ENTRY_SIZE`].
+pub fn safe_entry_path<R: Read>(entry: &zip::read::ZipFile<'_, R>) -> Result<PathBuf, String> {
+    let name = entry.name();
+    // Zip names use '/', but Windows-made archives sometimes carry '\\'
+    let escapes = name.starts_with(['/', '\\'])
+        || name.split(['/', '\\']).any(|part| part == "..")
+        || Path::new(name)
+            .components()
+            .any(|c| matches!(c, Component::RootDir | Component::Prefix(_)));
+    if escapes {
+        return Err(format!("Unsafe path in archive: {}", name));
+    }
+    if entry.is_symlink() {
+        return Err(format!("Symlink entries are not allowed: {}", name));
+    }
+    if entry.size() > MAX_ENTRY_SIZE {
+        return Err(format!(
+            "Archive entry too large: {} ({} bytes)",
+            name,
+            entry.size()
+        ));
+    }
+    entry
+        .enclosed_name()
+        .ok_or_else(|| format!("Unsafe path in archive: {}", name))
+}
+
+/// Extracts every entry whose name starts with `prefix` (e.g. "obb/") into `dest`.
+/// An empty prefix extracts the whole archive. Returns the number of files written.
+pub fn extract_directory<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    prefix: &str,
+    dest: &Path,
+) -> Result<usize, String> {
+    let mut written = 0;
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+        if !entry.name().starts_with(prefix) {
+            continue;
+        }
+
+        let relative = safe_entry_path(&entry)?;
+        let relative = relative
+            .strip_prefix(prefix)
+            .unwrap_or(&relative)
+            .to_path_buf();
+        let out_path = dest.join(&relative);
+
+        if entry.is_dir() {
+            fs::create_dir_all(&out_path).map_err(|e| e.to_string())?;
+            continue;
+        }
+
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let mut out = File::create(&out_path)
+            .map_err(|e| format!("Failed to extract {}: {}", entry.name(), e))?;
+        std::io::copy(&mut entry, &mut out)
+            .map_err(|e| format!("Failed to extract {}: {}", entry.name(), e))?;
+
+        if let Some(time) = entry.last_modified().and_then(from_zip_time) {
+            let _ = out.set_modified(time);
+        }
+        written += 1;
+    }
+
+    Ok(written)
+}
+
+/// Pushes a local folder tree to `remote_dir` on the device.
+///
+/// Directories are created up front (`adb push` skips empty ones); `adb push`
+/// carries the local modification times over to the device.
+pub fn push_directory(device_id: &str, local_dir: &Path, remote_dir: &str) -> Result<(), String> {
+    let remote_dir = remote_dir.trim_end_matches('/');
+    let mut remote_dirs = vec![remote_dir.to_string()];
+
+    let mut stack = vec![local_dir.to_path_buf()];
+    while let Some(current) = stack.pop() {
+        for entry in fs::read_dir(&current).map_err(|e| e.to_string())?.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                let relative = path
+                    .strip_prefix(local_dir)
+                    .map_err(|e| e.to_string())?
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                remote_dirs.push(format!("{}/{}", remote_dir, relative));
+                stack.push(path);
+            }
+        }
+    }
+
+    // Batch mkdir calls to keep the number of adb round trips low
+    for batch in remote_dirs.chunks(50) {
+        let quoted: Vec<String> = batch.iter().map(|d| format!("'{}'", d)).collect();
+        let mkdir_cmd = format!("mkdir -p {}", quoted.join(" "));
+        run_command(&["-s", device_id, "shell", &mkdir_cmd])?;
+    }
+
+    let local_contents = local_dir.join(".").to_string_lossy().to_string();
+    run_command(&["-s", device_id, "push", &local_contents, remote_dir])?;
+    Ok(())
+}
@@ -0,0 +1,321 @@
+//! Backup catalog: an index of `.easybckp` files across several folders.
+//!
+//! Each archive's `metadata.json` is read once and cached in
+//! `backup_catalog.json`; on refresh only new or modified files are re-read.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::backup_manifest::{self, BackupManifest};
+use crate::package_info::{PackageDetails, SourceDevice};
+
+/// Katalogdaki tek bir yedek
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    /// File modification time (seconds); used to detect changed archives
+    pub modified: u64,
+    /// RFC 3339 backup date from metadata, file time for legacy backups
+    pub backup_date: String,
+    /// Unix timestamp of `backup_date`, for sorting
+    pub backup_timestamp: i64,
+    pub package_name: Option<String>,
+    pub has_data: bool,
+    pub has_obb: bool,
+    pub has_external_data: bool,
+    pub app: Option<PackageDetails>,
+    pub source_device: Option<SourceDevice>,
+}
+
+/// Katalog sorgusu; boş alanlar filtre uygulamaz
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+pub struct CatalogQuery {
+    pub package_name: Option<String>,
+    /// Matches the source device serial or model (case-insensitive)
+    pub device: Option<String>,
+    pub has_data: Option<bool>,
+    /// Oldest first instead of newest first
+    pub oldest_first: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CatalogIndex {
+    folders: Vec<String>,
+    entries: HashMap<String, CatalogEntry>,
+}
+
+/// Backup Catalog yöneticisi
+pub struct BackupCatalog {
+    index_path: PathBuf,
+    index: Mutex<CatalogIndex>,
+}
+
+fn file_modified_secs(meta: &fs::Metadata) -> u64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Reads a single backup into a catalog entry.
+pub fn read_entry(path: &Path) -> Result<CatalogEntry, String> {
+    let file_meta = fs::metadata(path).map_err(|e| e.to_string())?;
+    // Unreadable or newer-format manifests are listed like legacy backups
+    let manifest =
+        backup_manifest::read_backup_manifest(path).unwrap_or_else(|_| BackupManifest::legacy());
+
+    let is_legacy = manifest.is_legacy();
+    let metadata_date = manifest
+        .backup_date
+        .as_deref()
+        .and_then(|d| chrono::DateTime::parse_from_rfc3339(d).ok());
+    let backup_date: chrono::DateTime<chrono::FixedOffset> = match metadata_date {
+        Some(date) => date,
+        None => {
+            let created = file_meta
+                .created()
+                .or_else(|_| file_meta.modified())
+                .unwrap_or(SystemTime::now());
+            chrono::DateTime::<chrono::Local>::from(created).fixed_offset()
+        }
+    };
+
+    Ok(CatalogEntry {
+        path: path.to_string_lossy().to_string(),
+        name: path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+        size: file_meta.len(),
+        modified: file_modified_secs(&file_meta),
+        backup_date: backup_date.to_rfc3339(),
+        backup_timestamp: backup_date.timestamp(),
+        package_name: manifest.package().map(|s| s.to_string()),
+        has_data: manifest.has_data || manifest.has_de_data || manifest.has_adb_data,
+        has_obb: manifest.has_obb,
+        has_external_data: manifest.has_external_data,
+        app: Some(manifest.app).filter(|_| !is_legacy),
+        source_device: manifest.source_device,
+    })
+}
+
+/// Recursively collects `*.easybckp` files. Symlinked folders are not followed.
+pub fn find_backup_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                stack.push(path);
+            } else if file_type.is_file() && path.extension().is_some_and(|e| e == "easybckp") {
+                files.push(path);
+            }
+        }
+    }
+
+    files
+}
+
+/// Adds the backups under `folder` to `entries`, reusing cached entries whose
+/// size and mtime are unchanged.
+fn scan_folder(
+    folder: &Path,
+    cached: &HashMap<String, CatalogEntry>,
+    entries: &mut HashMap<String, CatalogEntry>,
+) {
+    for path in find_backup_files(folder) {
+        let key = path.to_string_lossy().to_string();
+        if entries.contains_key(&key) {
+            continue;
+        }
+        let Ok(file_meta) = fs::metadata(&path) else {
+            continue;
+        };
+
+        let entry = match cached.get(&key) {
+            Some(old)
+                if old.size == file_meta.len()
+                    && old.modified == file_modified_secs(&file_meta) =>
+            {
+                old.clone()
+            }
+            _ => match read_entry(&path) {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            },
+        };
+        entries.insert(key, entry);
+    }
+}
+
+impl BackupCatalog {
+    pub fn new(app_handle: &AppHandle) -> Self {
+        let app_dir = app_handle
+            .path()
+            .app_data_dir()
+            .unwrap_or_else(|_| PathBuf::from("."));
+        if !app_dir.exists() {
+            let _ = fs::create_dir_all(&app_dir);
+        }
+
+        let index_path = app_dir.join("backup_catalog.json");
+        let index = fs::read_to_string(&index_path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<CatalogIndex>(&content).ok())
+            .unwrap_or_default();
+
+        BackupCatalog {
+            index_path,
+            index: Mutex::new(index),
+        }
+    }
+
+    fn save(&self, index: &CatalogIndex) {
+        if let Ok(json) = serde_json::to_string(index) {
+            let _ = fs::write(&self.index_path, json);
+        }
+    }
+
+    pub fn folders(&self) -> Vec<String> {
+        self.index.lock().unwrap().folders.clone()
+    }
+
+    /// Adds `folder` to the indexed folders unless it's already covered by one.
+    pub fn ensure_folder(&self, folder: &Path) {
+        let mut index = self.index.lock().unwrap();
+        if index.folders.iter().any(|f| folder.starts_with(f)) {
+            return;
+        }
+        index.folders.push(folder.to_string_lossy().to_string());
+        self.save(&index);
+    }
+
+    /// Replaces the configured folders and re-indexes.
+    pub fn set_folders(&self, folders: Vec<String>) -> usize {
+        {
+            let mut index = self.index.lock().unwrap();
+            index.folders = folders;
+            index.folders.sort();
+            index.folders.dedup();
+        }
+        self.refresh()
+    }
+
+    /// Rescans all folders. Unchanged archives (same size and mtime) keep their
+    /// cached entry; removed files drop out. Returns the number of indexed backups.
+    pub fn refresh(&self) -> usize {
+        let (folders, cached) = {
+            let index = self.index.lock().unwrap();
+            (index.folders.clone(), index.entries.clone())
+        };
+
+        // Metadata reads happen without holding the lock
+        let mut entries = HashMap::new();
+        for folder in &folders {
+            scan_folder(Path::new(folder), &cached, &mut entries);
+        }
+
+        let mut index = self.index.lock().unwrap();
+        index.entries = entries;
+        self.save(&index);
+        index.entries.len()
+    }
+
+    /// Indexes `folder` (adding it to the catalog folders) and returns its
+    /// backups. Like `refresh`, only new or modified archives are re-read.
+    pub fn list_folder(&self, folder: &Path) -> Vec<CatalogEntry> {
+        self.ensure_folder(folder);
+        let cached = self.index.lock().unwrap().entries.clone();
+
+        let mut found = HashMap::new();
+        scan_folder(folder, &cached, &mut found);
+
+        let mut index = self.index.lock().unwrap();
+        index
+            .entries
+            .retain(|path, _| !Path::new(path).starts_with(folder));
+        index.entries.extend(found.clone());
+        self.save(&index);
+        found.into_values().collect()
+    }
+
+    /// Adds or updates a single backup (e.g. right after it was created).
+    pub fn upsert(&self, path: &Path) -> Result<CatalogEntry, String> {
+        let entry = read_entry(path)?;
+        let mut index = self.index.lock().unwrap();
+        index.entries.insert(entry.path.clone(), entry.clone());
+        self.save(&index);
+        Ok(entry)
+    }
+
+    /// Deletes a backup file and drops it from the index. Shared by the
+    /// `delete_backup` command and retention pruning.
+    pub fn delete_backup(&self, path: &Path) -> Result<(), String> {
+        if !path.exists() {
+            return Err("Backup file not found".to_string());
+        }
+        fs::remove_file(path).map_err(|e| format!("Failed to delete backup: {}", e))?;
+        self.remove(path);
+        Ok(())
+    }
+
+    pub fn remove(&self, path: &Path) {
+        let key = path.to_string_lossy().to_string();
+        let mut index = self.index.lock().unwrap();
+        if index.entries.remove(&key).is_some() {
+            self.save(&index);
+        }
+    }
+
+    pub fn query(&self, query: &CatalogQuery) -> Vec<CatalogEntry> {
+        let device = query.device.as_ref().map(|d| d.to_lowercase());
+        let index = self.index.lock().unwrap();
+
+        let mut results: Vec<CatalogEntry> = index
+            .entries
+            .values()
+            .filter(|e| {
+                query
+                    .package_name
+                    .as_ref()
+                    .map_or(true, |p| e.package_name.as_ref() == Some(p))
+            })
+            .filter(|e| query.has_data.map_or(true, |d| e.has_data == d))
+            .filter(|e| {
+                device.as_ref().map_or(true, |d| {
+                    e.source_device.as_ref().is_some_and(|src| {
+                        [&src.serial, &src.model]
+                            .iter()
+                            .any(|v| v.as_ref().is_some_and(|v| v.to_lowercase() == *d))
+                    })
+                })
+            })
+            .cloned()
+            .collect();
+
+        if query.oldest_first {
+            results.sort_by_key(|e| e.backup_timestamp);
+        } else {
+            results.sort_by_key(|e| std::cmp::Reverse(e.backup_timestamp));
+        }
+        results
+    }
+}
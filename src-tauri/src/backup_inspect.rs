@@ -0,0 +1,181 @@
+//! Read-only view of an `.easybckp`: entries, metadata, split set and components,
+//! plus extraction of a single component to a PC folder.
+
+use serde::Serialize;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use crate::archive;
+use crate::backup_manifest::{self, BackupManifest};
+use crate::splits::{self, SplitApk};
+
+/// Component ids and the archive prefix / entry they map to
+const COMPONENTS: &[(&str, &str)] = &[
+    ("apk", "apks/"),
+    ("obb", "obb/"),
+    ("external_data", "extdata/"),
+    ("data", "data.tar.gz"),
+    ("data_de", "data_de.tar.gz"),
+    ("adb_data", "adb_data.tar"),
+    ("permissions", "permissions.json"),
+    ("metadata", "metadata.json"),
+];
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ArchiveEntryInfo {
+    pub name: String,
+    pub size: u64,
+    pub compressed_size: u64,
+    pub is_dir: bool,
+}
+
+/// Yedek bileşeni (apk, obb, data...) ve toplam boyutu
+#[derive(Serialize, Debug, Clone)]
+pub struct BackupComponent {
+    pub id: String,
+    pub size: u64,
+    pub file_count: usize,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BackupInspection {
+    pub path: String,
+    pub size: u64,
+    /// `None` for legacy backups
+    pub metadata: Option<BackupManifest>,
+    /// Why the manifest couldn't be read (corrupt, or written by a newer app version)
+    pub metadata_error: Option<String>,
+    pub entries: Vec<ArchiveEntryInfo>,
+    pub splits: Vec<SplitApk>,
+    pub components: Vec<BackupComponent>,
+    /// Legacy format: a single `base.apk` at the archive root
+    pub is_legacy: bool,
+}
+
+fn component_of(name: &str) -> Option<&'static str> {
+    if name == "base.apk" {
+        return Some("apk");
+    }
+    COMPONENTS
+        .iter()
+        .find(|(_, prefix)| {
+            if prefix.ends_with('/') {
+                name.starts_with(prefix)
+            } else {
+                name == *prefix
+            }
+        })
+        .map(|(id, _)| *id)
+}
+
+pub fn inspect_backup(backup_path: &Path) -> Result<BackupInspection, String> {
+    let size = fs::metadata(backup_path).map_err(|e| e.to_string())?.len();
+    let file = File::open(backup_path).map_err(|e| format!("Failed to open backup: {}", e))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read zip: {}", e))?;
+
+    let mut entries = Vec::new();
+    let mut components: Vec<BackupComponent> = Vec::new();
+    let mut apk_names = Vec::new();
+
+    for i in 0..archive.len() {
+        let entry = archive.by_index_raw(i).map_err(|e| e.to_string())?;
+        let name = entry.name().to_string();
+        let is_dir = entry.is_dir();
+
+        if !is_dir {
+            if let Some(id) = component_of(&name) {
+                match components.iter_mut().find(|c| c.id == id) {
+                    Some(c) => {
+                        c.size += entry.size();
+                        c.file_count += 1;
+                    }
+                    None => components.push(BackupComponent {
+                        id: id.to_string(),
+                        size: entry.size(),
+                        file_count: 1,
+                    }),
+                }
+                if id == "apk" {
+                    apk_names.push(PathBuf::from(&name));
+                }
+            }
+        }
+
+        entries.push(ArchiveEntryInfo {
+            name,
+            size: entry.size(),
+            compressed_size: entry.compressed_size(),
+            is_dir,
+        });
+    }
+
+    // Split classification by file name only; the archive isn't extracted
+    let splits = splits::analyze_splits(&apk_names);
+    let is_legacy = entries.iter().any(|e| e.name == "base.apk");
+    let (metadata, metadata_error) = match backup_manifest::read_manifest(&mut archive) {
+        Ok(manifest) if manifest.is_legacy() => (None, None),
+        Ok(manifest) => (Some(manifest), None),
+        Err(e) => (None, Some(e)),
+    };
+
+    Ok(BackupInspection {
+        path: backup_path.to_string_lossy().to_string(),
+        size,
+        metadata,
+        metadata_error,
+        entries,
+        splits,
+        components,
+        is_legacy,
+    })
+}
+
+/// Extracts a component (`apk`, `obb`, `data`...) or a single entry
+/// (e.g. `apks/base.apk`) into `dest_dir`. Returns the written paths.
+pub fn extract_component(
+    backup_path: &Path,
+    component: &str,
+    dest_dir: &Path,
+) -> Result<Vec<PathBuf>, String> {
+    let file = File::open(backup_path).map_err(|e| format!("Failed to open backup: {}", e))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read zip: {}", e))?;
+    fs::create_dir_all(dest_dir).map_err(|e| e.to_string())?;
+
+    let target = COMPONENTS
+        .iter()
+        .find(|(id, _)| *id == component)
+        .map(|(_, prefix)| *prefix)
+        .unwrap_or(component);
+
+    // Legacy backups keep the APK at the root
+    let target = if target == "apks/" && !archive.file_names().any(|n| n.starts_with("apks/")) {
+        "base.apk"
+    } else {
+        target
+    };
+
+    if target.ends_with('/') {
+        let out_dir = dest_dir.join(target.trim_end_matches('/'));
+        let count = archive::extract_directory(&mut archive, target, &out_dir)?;
+        if count == 0 {
+            return Err(format!("Backup has no {} component", component));
+        }
+        return Ok(vec![out_dir]);
+    }
+
+    let mut entry = archive
+        .by_name(target)
+        .map_err(|_| format!("Backup has no {} component", component))?;
+    let file_name = archive::safe_entry_path(&entry)?
+        .file_name()
+        .map(|n| n.to_owned())
+        .ok_or_else(|| format!("Unsafe path in archive: {}", entry.name()))?;
+    let out_path = dest_dir.join(file_name);
+    let mut out = File::create(&out_path).map_err(|e| e.to_string())?;
+    std::io::copy(&mut entry, &mut out)
+        .map_err(|e| format!("Failed to extract {}: {}", target, e))?;
+
+    Ok(vec![out_path])
+}
@@ -0,0 +1,181 @@
+//! Typed, versioned `metadata.json` of an `.easybckp`.
+//!
+//! Format history:
+//! * v1: legacy, a single `base.apk` at the archive root and no metadata
+//! * v3: `apks/` folder plus metadata with a free-form `"version": "3.0"`
+//! * v4: same layout, numeric `formatVersion` and the writing app's version
+//!
+//! Older manifests are migrated on read. Backups written by a newer app
+//! version are rejected with a clear error instead of being half-restored.
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+
+use crate::package_info::{PackageDetails, SourceDevice};
+
+/// Format written by this build
+pub const FORMAT_VERSION: u32 = 4;
+pub const LEGACY_FORMAT_VERSION: u32 = 1;
+
+/// metadata.json is a few KB; anything bigger isn't ours
+const MAX_MANIFEST_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Yedek manifestosu (metadata.json)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    /// EasyADB version that wrote the backup
+    pub created_by: Option<String>,
+    pub package_name: String,
+    pub original_path: Option<String>,
+    /// RFC 3339
+    pub backup_date: Option<String>,
+    pub is_split: bool,
+    pub has_obb: bool,
+    /// Root `data.tar.gz` (credential-encrypted data dir)
+    pub has_data: bool,
+    /// Root `data_de.tar.gz` (device-encrypted data dir)
+    pub has_de_data: bool,
+    /// Android user the root data was taken from
+    pub data_user: Option<u32>,
+    /// `adb backup` fallback `adb_data.tar`
+    pub has_adb_data: bool,
+    pub adb_backup_version: Option<u32>,
+    pub has_external_data: bool,
+    pub has_permissions: bool,
+    pub external_data_size: Option<u64>,
+    pub external_data_excludes: Vec<String>,
+    pub app: PackageDetails,
+    pub source_device: Option<SourceDevice>,
+}
+
+impl BackupManifest {
+    /// Manifest for a v1 backup, which carries no metadata at all.
+    pub fn legacy() -> Self {
+        BackupManifest {
+            format_version: LEGACY_FORMAT_VERSION,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_legacy(&self) -> bool {
+        self.format_version == LEGACY_FORMAT_VERSION
+    }
+
+    /// Package name, `None` for legacy backups.
+    pub fn package(&self) -> Option<&str> {
+        Some(self.package_name.as_str()).filter(|p| !p.is_empty())
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| e.to_string())
+    }
+}
+
+/// Reads the format version of a raw manifest. v3 wrote `"version": "3.0"`.
+fn detect_version(value: &serde_json::Value) -> Result<u32, String> {
+    if let Some(version) = value["formatVersion"].as_u64() {
+        return Ok(version as u32);
+    }
+    match value["version"].as_str() {
+        Some(version) => version
+            .split('.')
+            .next()
+            .and_then(|major| major.trim().parse().ok())
+            .ok_or_else(|| format!("Unknown backup format version \"{}\"", version)),
+        None => Ok(3),
+    }
+}
+
+/// v3 -> v4: the string version is replaced by `formatVersion`.
+fn migrate_v3(mut value: serde_json::Value) -> serde_json::Value {
+    if let Some(obj) = value.as_object_mut() {
+        obj.remove("version");
+        obj.insert("formatVersion".to_string(), serde_json::json!(4));
+    }
+    value
+}
+
+/// Parses `metadata.json` content of any known format version.
+pub fn parse(content: &[u8]) -> Result<BackupManifest, String> {
+    let mut value: serde_json::Value =
+        serde_json::from_slice(content).map_err(|e| format!("Invalid backup metadata: {}", e))?;
+    if !value.is_object() {
+        return Err("Invalid backup metadata: not a JSON object".to_string());
+    }
+
+    let version = detect_version(&value)?;
+    if version > FORMAT_VERSION {
+        let created_by = value["createdBy"]
+            .as_str()
+            .map(|v| format!(" by EasyADB {}", v))
+            .unwrap_or_default();
+        return Err(format!(
+            "This backup was created{} with format v{}, but this version of EasyADB only supports up to v{}. Update EasyADB to restore it.",
+            created_by, version, FORMAT_VERSION
+        ));
+    }
+
+    if version <= 3 {
+        value = migrate_v3(value);
+    }
+
+    let manifest: BackupManifest =
+        serde_json::from_value(value).map_err(|e| format!("Invalid backup metadata: {}", e))?;
+    if manifest.package_name.is_empty() {
+        return Err("Invalid backup metadata: missing packageName".to_string());
+    }
+    // The name ends up in (root) shell commands and local paths
+    if !is_valid_package_name(&manifest.package_name) {
+        return Err(format!(
+            "Invalid backup metadata: bad packageName {:?}",
+            manifest.package_name
+        ));
+    }
+    Ok(manifest)
+}
+
+/// Android package name grammar: two or more dot-separated segments, each
+/// starting with a letter and made of letters, digits and `_`.
+pub fn is_valid_package_name(name: &str) -> bool {
+    let segments: Vec<&str> = name.split('.').collect();
+    segments.len() >= 2
+        && segments.iter().all(|segment| {
+            let mut chars = segment.chars();
+            chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+}
+
+/// Reads and migrates the manifest of an opened archive.
+/// Archives without `metadata.json` are treated as legacy (v1) backups.
+pub fn read_manifest<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+) -> Result<BackupManifest, String> {
+    let mut entry = match archive.by_name("metadata.json") {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(BackupManifest::legacy()),
+        Err(e) => return Err(format!("Failed to read metadata: {}", e)),
+    };
+    if entry.size() > MAX_MANIFEST_SIZE {
+        return Err("Invalid backup metadata: metadata.json is too large".to_string());
+    }
+
+    let mut content = Vec::new();
+    (&mut entry)
+        .take(MAX_MANIFEST_SIZE)
+        .read_to_end(&mut content)
+        .map_err(|e| format!("Failed to read metadata: {}", e))?;
+    parse(&content)
+}
+
+/// Reads the manifest straight from a backup file without extracting it.
+pub fn read_backup_manifest(backup_path: &Path) -> Result<BackupManifest, String> {
+    let file = File::open(backup_path).map_err(|e| format!("Failed to open backup: {}", e))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read zip: {}", e))?;
+    read_manifest(&mut archive)
+}
@@ -0,0 +1,170 @@
+//! Backup file naming / folder layout templates.
+//!
+//! `{device_model}/{label}/{versionName}_{date}` ->
+//! `Pixel 8/WhatsApp/2.24.1_2026-10-01.easybckp`
+//!
+//! Placeholders: `{device_model}`, `{serial}`, `{package}`, `{label}`,
+//! `{versionName}`, `{date}` (YYYY-MM-DD), `{datetime}` (YYYYmmdd_HHMMSS).
+//! `/` in the template creates sub folders; values are sanitized so they can't.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::package_info;
+
+/// Matches the historical flat `<package>_<YYYYmmdd_HHMMSS>.easybckp` naming
+pub const DEFAULT_TEMPLATE: &str = "{package}_{datetime}";
+
+/// Şablon değerleri
+#[derive(Debug, Clone)]
+pub struct NamingContext {
+    pub device_model: String,
+    pub serial: String,
+    pub package: String,
+    pub label: String,
+    pub version_name: String,
+    pub date: chrono::DateTime<chrono::Local>,
+}
+
+impl NamingContext {
+    /// Queries model and version from the device. `label` falls back to the package name.
+    pub fn collect(device_id: &str, package_name: &str, label: Option<String>) -> Self {
+        NamingContext {
+            device_model: package_info::get_prop(device_id, "ro.product.model")
+                .unwrap_or_else(|| device_id.to_string()),
+            serial: device_id.to_string(),
+            package: package_name.to_string(),
+            label: label
+                .filter(|l| !l.trim().is_empty())
+                .unwrap_or_else(|| package_name.to_string()),
+            version_name: package_info::query_package_details(device_id, package_name)
+                .version_name
+                .unwrap_or_else(|| "unknown".to_string()),
+            date: chrono::Local::now(),
+        }
+    }
+
+    /// Example values for previews in the settings screen.
+    pub fn sample() -> Self {
+        NamingContext {
+            device_model: "Pixel 8".to_string(),
+            serial: "38051FDJH000XC".to_string(),
+            package: "com.whatsapp".to_string(),
+            label: "WhatsApp".to_string(),
+            version_name: "2.24.1".to_string(),
+            date: chrono::Local::now(),
+        }
+    }
+
+    fn value(&self, placeholder: &str) -> Option<String> {
+        Some(match placeholder {
+            "device_model" => self.device_model.clone(),
+            "serial" => self.serial.clone(),
+            "package" => self.package.clone(),
+            "label" => self.label.clone(),
+            "versionName" => self.version_name.clone(),
+            "date" => self.date.format("%Y-%m-%d").to_string(),
+            "datetime" => self.date.format("%Y%m%d_%H%M%S").to_string(),
+            _ => return None,
+        })
+    }
+}
+
+/// Makes a value safe as a single path component on Windows and Unix.
+pub fn sanitize_component(value: &str) -> String {
+    let cleaned: String = value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    // Windows drops trailing dots and spaces
+    let cleaned = cleaned.trim().trim_end_matches('.').trim().to_string();
+    if cleaned.is_empty() || cleaned == ".." {
+        "_".to_string()
+    } else {
+        cleaned
+    }
+}
+
+fn render_segment(segment: &str, ctx: &NamingContext) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = segment;
+
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("Unclosed placeholder in \"{}\"", segment))?;
+        let name = &rest[start + 1..start + end];
+        let value = ctx
+            .value(name)
+            .ok_or_else(|| format!("Unknown placeholder {{{}}}", name))?;
+        out.push_str(&sanitize_component(&value));
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(sanitize_component(&out))
+}
+
+/// Renders a template into a relative path ending in `.easybckp`.
+pub fn render(template: &str, ctx: &NamingContext) -> Result<PathBuf, String> {
+    let template = if template.trim().is_empty() {
+        DEFAULT_TEMPLATE
+    } else {
+        template.trim()
+    };
+
+    let mut path = PathBuf::new();
+    let segments: Vec<&str> = template
+        .split(['/', '\\'])
+        .filter(|s| !s.trim().is_empty())
+        .collect();
+    for (i, segment) in segments.iter().enumerate() {
+        let rendered = render_segment(segment, ctx)?;
+        if i == segments.len() - 1 {
+            path.push(format!("{}.easybckp", rendered));
+        } else {
+            path.push(rendered);
+        }
+    }
+
+    if path.as_os_str().is_empty() {
+        return Err("Backup name template is empty".to_string());
+    }
+    Ok(path)
+}
+
+/// Appends " (2)", " (3)"... when the file already exists.
+pub fn unique_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let parent = path.parent().unwrap_or(Path::new(""));
+
+    (2..)
+        .map(|n| parent.join(format!("{} ({}).easybckp", stem, n)))
+        .find(|candidate| !candidate.exists())
+        .unwrap()
+}
+
+/// Full destination path under `backup_root`, with parent folders created.
+pub fn resolve_backup_path(
+    backup_root: &Path,
+    template: Option<&str>,
+    ctx: &NamingContext,
+) -> Result<PathBuf, String> {
+    let relative = render(template.unwrap_or(DEFAULT_TEMPLATE), ctx)?;
+    let path = unique_path(&backup_root.join(relative));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+    }
+    Ok(path)
+}
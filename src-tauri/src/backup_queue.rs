@@ -0,0 +1,500 @@
+//! Persistent batch backup queue.
+//!
+//! A job holds a list of packages for one device. Workers (configurable
+//! concurrency) take pending items one by one; the queue is saved to
+//! `backup_queue.json` after every state change. If the device disconnects the
+//! job pauses and resumes automatically when it comes back, also after an app
+//! restart. Leftover temp folders and partial archives are removed on failure.
+//!
+//! The `adb backup` password is never saved, so a job that uses an encrypted
+//! fallback stops as `PasswordRequired` after a restart until it is resumed
+//! with the password again.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::adb::{self, AppPackage, BackupOptions};
+use crate::backup_catalog::BackupCatalog;
+use crate::backup_naming::{self, NamingContext};
+use crate::package_db::PackageDB;
+
+const MAX_CONCURRENCY: usize = 4;
+const RESUME_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemStatus {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    /// Device disconnected; resumes when it is back
+    WaitingForDevice,
+    /// App restarted and the `adb backup` password was lost; resume with it
+    PasswordRequired,
+    Completed,
+    Cancelled,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JobItem {
+    pub package: AppPackage,
+    pub status: ItemStatus,
+    pub backup_path: Option<String>,
+    pub size: u64,
+    pub error: Option<String>,
+    pub attempts: u32,
+}
+
+/// Toplu yedekleme işi
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BackupJob {
+    pub id: String,
+    pub device_id: String,
+    pub destination: Option<String>,
+    pub name_template: Option<String>,
+    pub options: BackupOptions,
+    /// The `adb backup` fallback runs with a password (kept in memory only)
+    #[serde(default)]
+    pub encrypted_adb_backup: bool,
+    pub concurrency: usize,
+    pub status: JobStatus,
+    pub items: Vec<JobItem>,
+    pub created_at: String,
+    pub finished_at: Option<String>,
+    /// Seconds spent processing, summed over resumes
+    #[serde(default)]
+    pub elapsed_secs: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FailedItem {
+    pub package: String,
+    pub error: String,
+}
+
+/// İş sonu özeti
+#[derive(Serialize, Debug, Clone)]
+pub struct JobSummary {
+    pub job_id: String,
+    pub status: JobStatus,
+    pub total: usize,
+    pub succeeded: usize,
+    pub pending: usize,
+    pub failed: Vec<FailedItem>,
+    pub total_bytes: u64,
+    pub elapsed_secs: u64,
+}
+
+#[derive(Serialize, Clone)]
+struct QueueProgress {
+    job_id: String,
+    package: String,
+    status: ItemStatus,
+    completed: usize,
+    total: usize,
+}
+
+impl BackupJob {
+    pub fn summary(&self) -> JobSummary {
+        JobSummary {
+            job_id: self.id.clone(),
+            status: self.status,
+            total: self.items.len(),
+            succeeded: self
+                .items
+                .iter()
+                .filter(|i| i.status == ItemStatus::Done)
+                .count(),
+            pending: self
+                .items
+                .iter()
+                .filter(|i| matches!(i.status, ItemStatus::Pending | ItemStatus::Running))
+                .count(),
+            failed: self
+                .items
+                .iter()
+                .filter(|i| i.status == ItemStatus::Failed)
+                .map(|i| FailedItem {
+                    package: i.package.name.clone(),
+                    error: i.error.clone().unwrap_or_default(),
+                })
+                .collect(),
+            total_bytes: self.items.iter().map(|i| i.size).sum(),
+            elapsed_secs: self.elapsed_secs,
+        }
+    }
+
+    fn completed(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|i| matches!(i.status, ItemStatus::Done | ItemStatus::Failed))
+            .count()
+    }
+}
+
+/// Backup Queue yöneticisi
+pub struct BackupQueue {
+    file_path: PathBuf,
+    jobs: Mutex<Vec<BackupJob>>,
+    /// Jobs with workers currently running
+    active: Mutex<HashSet<String>>,
+}
+
+fn device_online(device_id: &str) -> bool {
+    adb::get_devices()
+        .map(|devices| {
+            devices
+                .iter()
+                .any(|d| d.id == device_id && d.state == "device")
+        })
+        .unwrap_or(false)
+}
+
+impl BackupQueue {
+    pub fn new(app_handle: &AppHandle) -> Self {
+        let app_dir = app_handle
+            .path()
+            .app_data_dir()
+            .unwrap_or_else(|_| PathBuf::from("."));
+        if !app_dir.exists() {
+            let _ = fs::create_dir_all(&app_dir);
+        }
+
+        let file_path = app_dir.join("backup_queue.json");
+        let mut jobs: Vec<BackupJob> = fs::read_to_string(&file_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        // Items that were running when the app closed start over
+        for job in &mut jobs {
+            for item in &mut job.items {
+                if item.status == ItemStatus::Running {
+                    item.status = ItemStatus::Pending;
+                }
+            }
+            if job.status == JobStatus::Running {
+                job.status = JobStatus::WaitingForDevice;
+            }
+            if job.encrypted_adb_backup && job.status == JobStatus::WaitingForDevice {
+                job.status = JobStatus::PasswordRequired;
+            }
+        }
+
+        let queue = BackupQueue {
+            file_path,
+            jobs: Mutex::new(jobs),
+            active: Mutex::new(HashSet::new()),
+        };
+        queue.save(&queue.jobs.lock().unwrap());
+        queue
+    }
+
+    fn save(&self, jobs: &[BackupJob]) {
+        if let Ok(json) = serde_json::to_string(jobs) {
+            let _ = fs::write(&self.file_path, json);
+        }
+    }
+
+    pub fn list(&self) -> Vec<BackupJob> {
+        self.jobs.lock().unwrap().clone()
+    }
+
+    pub fn get(&self, job_id: &str) -> Option<BackupJob> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|j| j.id == job_id)
+            .cloned()
+    }
+
+    fn update<R>(&self, job_id: &str, f: impl FnOnce(&mut BackupJob) -> R) -> Option<R> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let result = jobs.iter_mut().find(|j| j.id == job_id).map(f);
+        self.save(&jobs);
+        result
+    }
+
+    pub fn enqueue(
+        &self,
+        device_id: String,
+        packages: Vec<AppPackage>,
+        destination: Option<String>,
+        name_template: Option<String>,
+        options: BackupOptions,
+        concurrency: usize,
+    ) -> BackupJob {
+        let now = chrono::Local::now();
+        let adb_backup_fallback = options.include_data && options.adb_backup_fallback;
+        let job = BackupJob {
+            id: format!("{}_{}", device_id, now.format("%Y%m%d_%H%M%S%3f")),
+            device_id,
+            destination,
+            name_template,
+            encrypted_adb_backup: adb_backup_fallback && options.adb_backup_password.is_some(),
+            options,
+            // Each `adb backup` waits for a confirmation on the device; only one at a time
+            concurrency: if adb_backup_fallback {
+                1
+            } else {
+                concurrency.clamp(1, MAX_CONCURRENCY)
+            },
+            status: JobStatus::Running,
+            items: packages
+                .into_iter()
+                .map(|package| JobItem {
+                    package,
+                    status: ItemStatus::Pending,
+                    backup_path: None,
+                    size: 0,
+                    error: None,
+                    attempts: 0,
+                })
+                .collect(),
+            created_at: now.to_rfc3339(),
+            finished_at: None,
+            elapsed_secs: 0,
+        };
+
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.push(job.clone());
+        self.save(&jobs);
+        job
+    }
+
+    /// Stops a job. Running items finish; no new ones are started.
+    pub fn cancel(&self, job_id: &str) -> bool {
+        self.update(job_id, |job| {
+            job.status = JobStatus::Cancelled;
+            job.finished_at = Some(chrono::Local::now().to_rfc3339());
+        })
+        .is_some()
+    }
+
+    /// Removes finished and cancelled jobs from the list.
+    pub fn clear_finished(&self) {
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.retain(|j| {
+            matches!(
+                j.status,
+                JobStatus::Running | JobStatus::WaitingForDevice | JobStatus::PasswordRequired
+            )
+        });
+        self.save(&jobs);
+    }
+
+    /// Marks the next pending item as running and returns it with its index.
+    fn take_next(&self, job_id: &str) -> Option<(usize, JobItem)> {
+        self.update(job_id, |job| {
+            if job.status != JobStatus::Running {
+                return None;
+            }
+            let index = job
+                .items
+                .iter()
+                .position(|i| i.status == ItemStatus::Pending)?;
+            let item = &mut job.items[index];
+            item.status = ItemStatus::Running;
+            item.attempts += 1;
+            Some((index, item.clone()))
+        })
+        .flatten()
+    }
+}
+
+/// Backs up one item. `Err(true)` means the device went away and the item should be retried.
+fn process_item(
+    app: &AppHandle,
+    job: &BackupJob,
+    item: &JobItem,
+) -> Result<(String, u64), (bool, String)> {
+    let package_name = &item.package.name;
+    let backup_dir = match job.destination.as_deref().filter(|d| !d.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            dirs::download_dir().ok_or((false, "Could not find Downloads directory".to_string()))?
+        }
+    };
+
+    let label = app.state::<PackageDB>().get(package_name);
+    let naming = NamingContext::collect(&job.device_id, package_name, label);
+    let dest_path =
+        backup_naming::resolve_backup_path(&backup_dir, job.name_template.as_deref(), &naming)
+            .map_err(|e| (false, e))?;
+
+    match adb::backup_package(
+        &job.device_id,
+        item.package.clone(),
+        dest_path.clone(),
+        &job.options,
+    ) {
+        Ok(_) => {
+            let catalog = app.state::<BackupCatalog>();
+            catalog.ensure_folder(&backup_dir);
+            let _ = catalog.upsert(&dest_path);
+            let size = fs::metadata(&dest_path).map(|m| m.len()).unwrap_or(0);
+            Ok((dest_path.to_string_lossy().to_string(), size))
+        }
+        Err(e) => {
+            // backup_package removes its own work folder but leaves a partial archive behind
+            let _ = fs::remove_file(&dest_path);
+            Err((!device_online(&job.device_id), e))
+        }
+    }
+}
+
+fn worker(app: &AppHandle, job_id: &str) {
+    let queue = app.state::<BackupQueue>();
+
+    while let Some((index, item)) = queue.take_next(job_id) {
+        let Some(job) = queue.get(job_id) else {
+            return;
+        };
+
+        let (status, path, size, error) = match process_item(app, &job, &item) {
+            Ok((path, size)) => (ItemStatus::Done, Some(path), size, None),
+            Err((true, error)) => {
+                // Device gone: put the item back and wait for reconnection
+                queue.update(job_id, |job| {
+                    job.items[index].status = ItemStatus::Pending;
+                    job.items[index].error = Some(error);
+                    if job.status == JobStatus::Running {
+                        job.status = JobStatus::WaitingForDevice;
+                    }
+                });
+                return;
+            }
+            Err((false, error)) => (ItemStatus::Failed, None, 0, Some(error)),
+        };
+
+        let (completed, total) = queue
+            .update(job_id, |job| {
+                let entry = &mut job.items[index];
+                entry.status = status;
+                entry.backup_path = path;
+                entry.size = size;
+                entry.error = error;
+                (job.completed(), job.items.len())
+            })
+            .unwrap_or((0, 0));
+
+        let _ = app.emit(
+            "backup-queue-progress",
+            QueueProgress {
+                job_id: job_id.to_string(),
+                package: item.package.name.clone(),
+                status,
+                completed,
+                total,
+            },
+        );
+    }
+}
+
+/// Starts (or resumes) a job on `concurrency` worker threads. `password`
+/// re-supplies the `adb backup` password of a `PasswordRequired` job.
+pub fn start_job(app: &AppHandle, job_id: &str, password: Option<String>) -> Result<(), String> {
+    let queue = app.state::<BackupQueue>();
+    if !queue.active.lock().unwrap().insert(job_id.to_string()) {
+        return Ok(());
+    }
+
+    let resumed = queue.update(job_id, |job| {
+        if password.is_some() {
+            job.options.adb_backup_password = password;
+        }
+        if job.encrypted_adb_backup && job.options.adb_backup_password.is_none() {
+            job.status = JobStatus::PasswordRequired;
+            return Err("The adb backup password is needed to resume this job".to_string());
+        }
+        if matches!(
+            job.status,
+            JobStatus::WaitingForDevice | JobStatus::PasswordRequired
+        ) {
+            job.status = JobStatus::Running;
+        }
+        Ok(job.concurrency)
+    });
+    let concurrency = match resumed {
+        Some(Ok(concurrency)) => concurrency,
+        Some(Err(e)) => {
+            queue.active.lock().unwrap().remove(job_id);
+            return Err(e);
+        }
+        None => {
+            queue.active.lock().unwrap().remove(job_id);
+            return Ok(());
+        }
+    };
+
+    let app = app.clone();
+    let job_id = job_id.to_string();
+    thread::spawn(move || {
+        let started = Instant::now();
+        let workers: Vec<_> = (0..concurrency)
+            .map(|_| {
+                let app = app.clone();
+                let job_id = job_id.clone();
+                thread::spawn(move || worker(&app, &job_id))
+            })
+            .collect();
+        for handle in workers {
+            let _ = handle.join();
+        }
+
+        let queue = app.state::<BackupQueue>();
+        let summary = queue.update(&job_id, |job| {
+            job.elapsed_secs += started.elapsed().as_secs();
+            let pending = job
+                .items
+                .iter()
+                .any(|i| matches!(i.status, ItemStatus::Pending | ItemStatus::Running));
+            if job.status == JobStatus::Running && !pending {
+                job.status = JobStatus::Completed;
+                job.finished_at = Some(chrono::Local::now().to_rfc3339());
+            }
+            job.summary()
+        });
+        queue.active.lock().unwrap().remove(&job_id);
+
+        if let Some(summary) = summary {
+            let _ = app.emit("backup-queue-finished", summary);
+        }
+    });
+    Ok(())
+}
+
+/// Watches for devices of paused jobs and resumes them. Called once from `setup`.
+pub fn start(app: AppHandle) {
+    thread::spawn(move || loop {
+        let waiting: Vec<(String, String)> = app
+            .state::<BackupQueue>()
+            .list()
+            .into_iter()
+            .filter(|j| j.status == JobStatus::WaitingForDevice)
+            .map(|j| (j.id, j.device_id))
+            .collect();
+
+        for (job_id, device_id) in waiting {
+            if device_online(&device_id) {
+                let _ = start_job(&app, &job_id, None);
+            }
+        }
+        thread::sleep(RESUME_POLL_INTERVAL);
+    });
+}
@@ -0,0 +1,391 @@
+//! Scheduled backups per device serial.
+//!
+//! A background thread polls `adb devices`; when a device with a schedule is
+//! connected (or stays connected) and its interval has elapsed, the selected
+//! packages are backed up. The package list comes from the device's cached
+//! profile (`DeviceCache`), refreshed at the start of each run; a schedule
+//! without packages covers every user app in it. Only one run per serial can
+//! be in progress at a time. Each run is appended to a history log and reported
+//! to the frontend through `scheduled-backup-*` events.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::adb::{self, BackupOptions};
+use crate::backup_catalog::BackupCatalog;
+use crate::backup_naming::{self, NamingContext};
+use crate::device_cache::DeviceCache;
+use crate::package_db::PackageDB;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+const MAX_HISTORY: usize = 200;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleTrigger {
+    /// Run when the device is plugged in (if the interval has elapsed)
+    OnConnect,
+    /// Run whenever the device is connected and the interval has elapsed
+    Interval,
+}
+
+/// Cihaz bazlı yedekleme planı
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BackupSchedule {
+    pub serial: String,
+    pub enabled: bool,
+    pub trigger: ScheduleTrigger,
+    /// Empty = every user app in the device's cached profile
+    pub packages: Vec<String>,
+    /// Minimum time between two runs (e.g. 24 = at most once a day)
+    pub interval_hours: u64,
+    /// Backup folder; Downloads when empty
+    pub destination: Option<String>,
+    pub include_data: bool,
+    pub include_obb: bool,
+    #[serde(default)]
+    pub include_external_data: bool,
+    /// File name / folder layout template (see `backup_naming`)
+    #[serde(default)]
+    pub name_template: Option<String>,
+    /// RFC 3339, set after each run
+    #[serde(default)]
+    pub last_run: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FailedBackup {
+    pub package: String,
+    pub error: String,
+}
+
+/// Tek bir zamanlanmış çalıştırmanın sonucu
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScheduleRun {
+    pub serial: String,
+    /// "connect", "interval" or "manual"
+    pub trigger: String,
+    pub started_at: String,
+    pub finished_at: String,
+    pub succeeded: Vec<String>,
+    pub failed: Vec<FailedBackup>,
+}
+
+#[derive(Serialize, Clone)]
+struct ScheduleProgress {
+    serial: String,
+    package: String,
+    current: usize,
+    total: usize,
+}
+
+/// Backup Scheduler yöneticisi
+pub struct BackupScheduler {
+    schedules_path: PathBuf,
+    history_path: PathBuf,
+    schedules: Mutex<HashMap<String, BackupSchedule>>,
+    history: Mutex<Vec<ScheduleRun>>,
+    /// Serials with a run in progress
+    running: Mutex<HashSet<String>>,
+}
+
+fn load_json<T: serde::de::DeserializeOwned + Default>(path: &PathBuf) -> T {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+impl BackupScheduler {
+    pub fn new(app_handle: &AppHandle) -> Self {
+        let app_dir = app_handle
+            .path()
+            .app_data_dir()
+            .unwrap_or_else(|_| PathBuf::from("."));
+        if !app_dir.exists() {
+            let _ = fs::create_dir_all(&app_dir);
+        }
+
+        let schedules_path = app_dir.join("backup_schedules.json");
+        let history_path = app_dir.join("backup_history.json");
+
+        BackupScheduler {
+            schedules: Mutex::new(load_json(&schedules_path)),
+            history: Mutex::new(load_json(&history_path)),
+            schedules_path,
+            history_path,
+            running: Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn save_schedules(&self, schedules: &HashMap<String, BackupSchedule>) {
+        if let Ok(json) = serde_json::to_string_pretty(schedules) {
+            let _ = fs::write(&self.schedules_path, json);
+        }
+    }
+
+    pub fn list(&self) -> Vec<BackupSchedule> {
+        self.schedules.lock().unwrap().values().cloned().collect()
+    }
+
+    pub fn set(&self, schedule: BackupSchedule) {
+        let mut schedules = self.schedules.lock().unwrap();
+        // Keep the last run time when the user edits an existing schedule
+        let last_run = schedules
+            .get(&schedule.serial)
+            .and_then(|s| s.last_run.clone());
+        let mut schedule = schedule;
+        if schedule.last_run.is_none() {
+            schedule.last_run = last_run;
+        }
+        schedules.insert(schedule.serial.clone(), schedule);
+        self.save_schedules(&schedules);
+    }
+
+    pub fn remove(&self, serial: &str) {
+        let mut schedules = self.schedules.lock().unwrap();
+        if schedules.remove(serial).is_some() {
+            self.save_schedules(&schedules);
+        }
+    }
+
+    pub fn history(&self, serial: Option<&str>) -> Vec<ScheduleRun> {
+        let history = self.history.lock().unwrap();
+        history
+            .iter()
+            .rev()
+            .filter(|run| serial.map_or(true, |s| run.serial == s))
+            .cloned()
+            .collect()
+    }
+
+    fn record(&self, run: ScheduleRun) {
+        {
+            let mut schedules = self.schedules.lock().unwrap();
+            if let Some(schedule) = schedules.get_mut(&run.serial) {
+                schedule.last_run = Some(run.started_at.clone());
+            }
+            self.save_schedules(&schedules);
+        }
+
+        let mut history = self.history.lock().unwrap();
+        history.push(run);
+        if history.len() > MAX_HISTORY {
+            let overflow = history.len() - MAX_HISTORY;
+            history.drain(..overflow);
+        }
+        if let Ok(json) = serde_json::to_string(&*history) {
+            let _ = fs::write(&self.history_path, json);
+        }
+    }
+
+    /// Marks `serial` as running. Returns false if a run is already in progress.
+    fn try_begin(&self, serial: &str) -> bool {
+        self.running.lock().unwrap().insert(serial.to_string())
+    }
+
+    fn finish(&self, serial: &str) {
+        self.running.lock().unwrap().remove(serial);
+    }
+
+    fn is_due(schedule: &BackupSchedule) -> bool {
+        let Some(last_run) = schedule
+            .last_run
+            .as_deref()
+            .and_then(|d| chrono::DateTime::parse_from_rfc3339(d).ok())
+        else {
+            return true;
+        };
+        let elapsed = chrono::Local::now().timestamp() - last_run.timestamp();
+        elapsed >= schedule.interval_hours as i64 * 3600
+    }
+}
+
+/// Backs up every package of `schedule`. Emits progress events and records the run.
+/// Callers must hold the serial's `running` slot.
+fn run_schedule(app: &AppHandle, schedule: &BackupSchedule, trigger: &str) -> ScheduleRun {
+    let scheduler = app.state::<BackupScheduler>();
+    let started_at = chrono::Local::now().to_rfc3339();
+    let mut run = ScheduleRun {
+        serial: schedule.serial.clone(),
+        trigger: trigger.to_string(),
+        started_at,
+        finished_at: String::new(),
+        succeeded: Vec::new(),
+        failed: Vec::new(),
+    };
+
+    let backup_dir = match &schedule.destination {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => dirs::download_dir(),
+    };
+    // Refreshes the cached profile for this serial
+    let installed = app
+        .state::<DeviceCache>()
+        .sync_backup_packages(&schedule.serial);
+
+    match (backup_dir, installed) {
+        (None, _) => run.failed.push(FailedBackup {
+            package: "*".to_string(),
+            error: "Could not find Downloads directory".to_string(),
+        }),
+        (_, Err(e)) => run.failed.push(FailedBackup {
+            package: "*".to_string(),
+            error: e,
+        }),
+        (Some(backup_dir), Ok(installed)) => {
+            let _ = fs::create_dir_all(&backup_dir);
+            let options = BackupOptions {
+                include_obb: schedule.include_obb,
+                include_data: schedule.include_data,
+                include_external_data: schedule.include_external_data,
+                ..Default::default()
+            };
+
+            let packages: Vec<String> = if schedule.packages.is_empty() {
+                installed
+                    .iter()
+                    .filter(|p| !p.is_system)
+                    .map(|p| p.name.clone())
+                    .collect()
+            } else {
+                schedule.packages.clone()
+            };
+
+            for (i, package_name) in packages.iter().enumerate() {
+                let _ = app.emit(
+                    "scheduled-backup-progress",
+                    ScheduleProgress {
+                        serial: schedule.serial.clone(),
+                        package: package_name.clone(),
+                        current: i + 1,
+                        total: packages.len(),
+                    },
+                );
+
+                let Some(cached) = installed.iter().find(|p| &p.name == package_name) else {
+                    run.failed.push(FailedBackup {
+                        package: package_name.clone(),
+                        error: "Not installed".to_string(),
+                    });
+                    continue;
+                };
+
+                let package = adb::AppPackage {
+                    name: cached.name.clone(),
+                    path: cached.path.clone(),
+                    is_system: cached.is_system,
+                };
+                let label = cached
+                    .label
+                    .clone()
+                    .or_else(|| app.state::<PackageDB>().get(package_name));
+                let naming = NamingContext::collect(&schedule.serial, package_name, label);
+                let dest_path = match backup_naming::resolve_backup_path(
+                    &backup_dir,
+                    schedule.name_template.as_deref(),
+                    &naming,
+                ) {
+                    Ok(path) => path,
+                    Err(error) => {
+                        run.failed.push(FailedBackup {
+                            package: package_name.clone(),
+                            error,
+                        });
+                        continue;
+                    }
+                };
+                match adb::backup_package(&schedule.serial, package, dest_path.clone(), &options) {
+                    Ok(_) => {
+                        let catalog = app.state::<BackupCatalog>();
+                        catalog.ensure_folder(&backup_dir);
+                        let _ = catalog.upsert(&dest_path);
+                        run.succeeded.push(package_name.clone());
+                    }
+                    Err(error) => run.failed.push(FailedBackup {
+                        package: package_name.clone(),
+                        error,
+                    }),
+                }
+            }
+        }
+    }
+
+    run.finished_at = chrono::Local::now().to_rfc3339();
+    scheduler.record(run.clone());
+    let _ = app.emit("scheduled-backup-finished", run.clone());
+    run
+}
+
+/// Runs a schedule on a worker thread unless one is already running for that serial.
+fn spawn_run(app: &AppHandle, schedule: BackupSchedule, trigger: &'static str) {
+    if !app.state::<BackupScheduler>().try_begin(&schedule.serial) {
+        return;
+    }
+
+    let app = app.clone();
+    thread::spawn(move || {
+        run_schedule(&app, &schedule, trigger);
+        app.state::<BackupScheduler>().finish(&schedule.serial);
+    });
+}
+
+/// Runs the schedule of `serial` right away on the calling thread. Refused
+/// while a scheduled run for the same serial is in progress, since both would
+/// share the same temp and on-device staging paths.
+pub fn run_now(app: &AppHandle, serial: &str) -> Result<ScheduleRun, String> {
+    let scheduler = app.state::<BackupScheduler>();
+    let schedule = scheduler
+        .list()
+        .into_iter()
+        .find(|s| s.serial == serial)
+        .ok_or_else(|| format!("No backup schedule for {}", serial))?;
+
+    if !scheduler.try_begin(serial) {
+        return Err(format!("A backup for {} is already running", serial));
+    }
+    let run = run_schedule(app, &schedule, "manual");
+    scheduler.finish(serial);
+    Ok(run)
+}
+
+/// Starts the device polling thread. Called once from `setup`.
+pub fn start(app: AppHandle) {
+    thread::spawn(move || {
+        let mut connected: HashSet<String> = HashSet::new();
+
+        loop {
+            let devices = adb::get_devices().unwrap_or_default();
+            let online: HashSet<String> = devices
+                .into_iter()
+                .filter(|d| d.state == "device")
+                .map(|d| d.id)
+                .collect();
+
+            let schedules = app.state::<BackupScheduler>().list();
+            for schedule in schedules {
+                if !schedule.enabled || !online.contains(&schedule.serial) {
+                    continue;
+                }
+                let just_connected = !connected.contains(&schedule.serial);
+                let trigger = match schedule.trigger {
+                    ScheduleTrigger::OnConnect if just_connected => "connect",
+                    ScheduleTrigger::OnConnect => continue,
+                    ScheduleTrigger::Interval => "interval",
+                };
+                if BackupScheduler::is_due(&schedule) {
+                    spawn_run(&app, schedule, trigger);
+                }
+            }
+
+            connected = online;
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
@@ -0,0 +1,368 @@
+//! Content-addressed, deduplicating backup repository.
+//!
+//! Instead of one self-contained `.easybckp` per run, every file of a backup
+//! (APKs, OBBs, data archive, metadata) is split into fixed-size chunks that
+//! are stored once under their SHA-256 hash. A snapshot is a small JSON
+//! manifest listing which chunks make up which archive entry.
+//!
+//! Layout:
+//!   <store>/objects/ab/cdef...   chunk contents
+//!   <store>/snapshots/<id>.json  snapshot manifests
+//!
+//! Ingest, delete, GC and export hold `STORE_LOCK`, so GC never sees chunks
+//! of a snapshot that is still being written.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use zip::write::FileOptions;
+
+use crate::backup_manifest;
+
+/// Chunk size for splitting archive entries. Identical APK/OBB files produce
+/// identical chunk lists, so they are stored only once.
+const CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Serializes operations that write to or sweep a store.
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+fn lock_store() -> MutexGuard<'static, ()> {
+    // A panicked holder leaves nothing half-written that the next one can't handle
+    STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StoreEntry {
+    pub path: String,
+    pub size: u64,
+    pub chunks: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StoreSnapshot {
+    pub id: String,
+    pub package_name: String,
+    pub backup_date: String,
+    pub total_size: u64,
+    /// Bytes actually written to the store when this snapshot was created
+    pub new_bytes: u64,
+    pub entries: Vec<StoreEntry>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GcReport {
+    pub removed_objects: usize,
+    pub freed_bytes: u64,
+}
+
+fn objects_dir(store_root: &Path) -> PathBuf {
+    store_root.join("objects")
+}
+
+fn snapshots_dir(store_root: &Path) -> PathBuf {
+    store_root.join("snapshots")
+}
+
+fn object_path(store_root: &Path, hash: &str) -> PathBuf {
+    objects_dir(store_root).join(&hash[..2]).join(&hash[2..])
+}
+
+fn snapshot_path(store_root: &Path, id: &str) -> PathBuf {
+    snapshots_dir(store_root).join(format!("{}.json", id))
+}
+
+/// Creates the store folder structure if it doesn't exist yet.
+pub fn init_store(store_root: &Path) -> Result<(), String> {
+    fs::create_dir_all(objects_dir(store_root))
+        .map_err(|e| format!("Failed to create store: {}", e))?;
+    fs::create_dir_all(snapshots_dir(store_root))
+        .map_err(|e| format!("Failed to create store: {}", e))?;
+    Ok(())
+}
+
+/// Writes a chunk under its hash. Returns the number of new bytes stored
+/// (0 if the chunk was already present).
+fn store_chunk(store_root: &Path, data: &[u8]) -> Result<(String, u64), String> {
+    let hash = format!("{:x}", Sha256::digest(data));
+    let path = object_path(store_root, &hash);
+    if path.exists() {
+        return Ok((hash, 0));
+    }
+
+    let parent = path.parent().ok_or("Invalid object path")?;
+    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+
+    // Write to a temp file first so an interrupted write never leaves a corrupt object
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, data).map_err(|e| format!("Failed to write object: {}", e))?;
+    fs::rename(&tmp_path, &path).map_err(|e| format!("Failed to write object: {}", e))?;
+
+    Ok((hash, data.len() as u64))
+}
+
+/// Reads up to `buf.len()` bytes, looping over short reads.
+fn read_chunk<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize, String> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = reader.read(&mut buf[filled..]).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
+
+/// Imports an existing `.easybckp` archive into the store as a new snapshot.
+pub fn ingest_archive(store_root: &Path, archive_path: &Path) -> Result<StoreSnapshot, String> {
+    let _lock = lock_store();
+    init_store(store_root)?;
+
+    let file = File::open(archive_path).map_err(|e| format!("Failed to open backup: {}", e))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read zip: {}", e))?;
+
+    let mut entries = Vec::new();
+    let mut total_size = 0;
+    let mut new_bytes = 0;
+    let mut package_name = String::new();
+    let mut backup_date = chrono::Local::now().to_rfc3339();
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+
+        let mut chunks = Vec::new();
+        let mut size = 0;
+        loop {
+            let n = read_chunk(&mut entry, &mut buffer)?;
+            if n == 0 {
+                break;
+            }
+            let (hash, written) = store_chunk(store_root, &buffer[..n])?;
+            chunks.push(hash);
+            size += n as u64;
+            new_bytes += written;
+        }
+
+        if name == "metadata.json" && size <= CHUNK_SIZE as u64 {
+            // Single chunk, still in the buffer
+            if let Ok(manifest) = backup_manifest::parse(&buffer[..size as usize]) {
+                package_name = manifest.package_name;
+                if let Some(d) = manifest.backup_date {
+                    backup_date = d;
+                }
+            }
+        }
+
+        total_size += size;
+        entries.push(StoreEntry {
+            path: name,
+            size,
+            chunks,
+        });
+    }
+
+    if package_name.is_empty() {
+        package_name = archive_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("unknown")
+            .to_string();
+    }
+
+    // Snapshot ID mirrors the standalone file naming: <package>_<timestamp>
+    let timestamp = chrono::DateTime::parse_from_rfc3339(&backup_date)
+        .map(|d| d.format("%Y%m%d_%H%M%S").to_string())
+        .unwrap_or_else(|_| chrono::Local::now().format("%Y%m%d_%H%M%S").to_string());
+    let mut id = format!("{}_{}", package_name, timestamp);
+    let mut counter = 2;
+    while snapshot_path(store_root, &id).exists() {
+        id = format!("{}_{}_{}", package_name, timestamp, counter);
+        counter += 1;
+    }
+
+    let snapshot = StoreSnapshot {
+        id,
+        package_name,
+        backup_date,
+        total_size,
+        new_bytes,
+        entries,
+    };
+
+    // Same temp + rename as objects: GC must never see a half-written manifest
+    let json = serde_json::to_string_pretty(&snapshot).map_err(|e| e.to_string())?;
+    let path = snapshot_path(store_root, &snapshot.id);
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, json).map_err(|e| format!("Failed to write snapshot: {}", e))?;
+    fs::rename(&tmp_path, &path).map_err(|e| format!("Failed to write snapshot: {}", e))?;
+
+    Ok(snapshot)
+}
+
+/// Lists all snapshots in the store, newest first.
+pub fn list_snapshots(store_root: &Path) -> Result<Vec<StoreSnapshot>, String> {
+    let mut snapshots = Vec::new();
+
+    if let Ok(entries) = fs::read_dir(snapshots_dir(store_root)) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                if let Ok(content) = fs::read_to_string(&path) {
+                    if let Ok(snapshot) = serde_json::from_str::<StoreSnapshot>(&content) {
+                        snapshots.push(snapshot);
+                    }
+                }
+            }
+        }
+    }
+
+    snapshots.sort_by(|a, b| b.backup_date.cmp(&a.backup_date));
+    Ok(snapshots)
+}
+
+/// Like `list_snapshots`, but fails on any manifest it can't read or parse.
+/// GC uses this: a skipped manifest would make its chunks look unreferenced.
+fn list_snapshots_strict(store_root: &Path) -> Result<Vec<StoreSnapshot>, String> {
+    let entries = match fs::read_dir(snapshots_dir(store_root)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to list snapshots: {}", e)),
+    };
+
+    let mut snapshots = Vec::new();
+    for entry in entries {
+        let path = entry
+            .map_err(|e| format!("Failed to list snapshots: {}", e))?
+            .path();
+        if !path.extension().is_some_and(|ext| ext == "json") {
+            continue;
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let snapshot = serde_json::from_str::<StoreSnapshot>(&content)
+            .map_err(|e| format!("Corrupt snapshot {}: {}", path.display(), e))?;
+        snapshots.push(snapshot);
+    }
+    Ok(snapshots)
+}
+
+/// Snapshot IDs come from the frontend; make sure they can't escape the store.
+fn validate_id(id: &str) -> Result<(), String> {
+    if id.is_empty() || id.contains(['/', '\\']) || id.contains("..") {
+        return Err(format!("Invalid snapshot ID: {}", id));
+    }
+    Ok(())
+}
+
+fn load_snapshot(store_root: &Path, id: &str) -> Result<StoreSnapshot, String> {
+    validate_id(id)?;
+    let content = fs::read_to_string(snapshot_path(store_root, id))
+        .map_err(|_| format!("Snapshot not found: {}", id))?;
+    serde_json::from_str(&content).map_err(|e| format!("Corrupt snapshot {}: {}", id, e))
+}
+
+/// Removes a snapshot manifest and garbage-collects chunks no longer referenced.
+pub fn delete_snapshot(store_root: &Path, id: &str) -> Result<GcReport, String> {
+    let _lock = lock_store();
+    validate_id(id)?;
+    let path = snapshot_path(store_root, id);
+    if !path.exists() {
+        return Err(format!("Snapshot not found: {}", id));
+    }
+    fs::remove_file(path).map_err(|e| format!("Failed to delete snapshot: {}", e))?;
+    sweep(store_root)
+}
+
+/// Mark & sweep: deletes every object not referenced by any snapshot.
+pub fn collect_garbage(store_root: &Path) -> Result<GcReport, String> {
+    let _lock = lock_store();
+    sweep(store_root)
+}
+
+fn sweep(store_root: &Path) -> Result<GcReport, String> {
+    let mut referenced = HashSet::new();
+    // Any unreadable manifest aborts the sweep instead of freeing its chunks
+    for snapshot in list_snapshots_strict(store_root)? {
+        for entry in snapshot.entries {
+            referenced.extend(entry.chunks);
+        }
+    }
+
+    let mut report = GcReport {
+        removed_objects: 0,
+        freed_bytes: 0,
+    };
+
+    let prefixes = match fs::read_dir(objects_dir(store_root)) {
+        Ok(p) => p,
+        Err(_) => return Ok(report),
+    };
+
+    for prefix in prefixes.flatten() {
+        let prefix_path = prefix.path();
+        if !prefix_path.is_dir() {
+            continue;
+        }
+        let prefix_name = prefix.file_name().to_string_lossy().to_string();
+
+        for object in fs::read_dir(&prefix_path)
+            .map_err(|e| e.to_string())?
+            .flatten()
+        {
+            let hash = format!("{}{}", prefix_name, object.file_name().to_string_lossy());
+            if !referenced.contains(&hash) {
+                let size = object.metadata().map(|m| m.len()).unwrap_or(0);
+                if fs::remove_file(object.path()).is_ok() {
+                    report.removed_objects += 1;
+                    report.freed_bytes += size;
+                }
+            }
+        }
+
+        // Drop empty prefix folders
+        let _ = fs::remove_dir(&prefix_path);
+    }
+
+    Ok(report)
+}
+
+/// Reassembles a snapshot into a standalone `.easybckp` in `dest_dir`.
+pub fn export_snapshot(store_root: &Path, id: &str, dest_dir: &Path) -> Result<PathBuf, String> {
+    let _lock = lock_store();
+    let snapshot = load_snapshot(store_root, id)?;
+
+    if !dest_dir.exists() {
+        fs::create_dir_all(dest_dir)
+            .map_err(|e| format!("Failed to create export directory: {}", e))?;
+    }
+    let dest_path = dest_dir.join(format!("{}.easybckp", snapshot.id));
+
+    let file = File::create(&dest_path).map_err(|e| e.to_string())?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = FileOptions::<()>::default().compression_method(zip::CompressionMethod::Stored);
+
+    for entry in &snapshot.entries {
+        let entry_options = options.large_file(entry.size >= u32::MAX as u64);
+        zip.start_file(entry.path.as_str(), entry_options)
+            .map_err(|e| e.to_string())?;
+        for hash in &entry.chunks {
+            let data = fs::read(object_path(store_root, hash))
+                .map_err(|_| format!("Missing object {} for {}", hash, entry.path))?;
+            zip.write_all(&data).map_err(|e| e.to_string())?;
+        }
+    }
+
+    zip.finish().map_err(|e| e.to_string())?;
+
+    Ok(dest_path)
+}
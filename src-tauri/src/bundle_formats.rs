@@ -0,0 +1,448 @@
+//! Third-party APK bundle formats.
+//!
+//! * `.apks` (SAI): split APKs at the root + `meta.sai_v2.json` / `meta.sai_v1.json`
+//! * `.xapk` (APKPure): split APKs + `manifest.json`, OBBs under `Android/obb/<pkg>/`
+//! * `.apkm` (APKMirror): split APKs + `info.json` (unencrypted variant only)
+//!
+//! Imports go through the same split-APK install and OBB push as `.easybckp`
+//! restores; `.easybckp` backups can be exported to `.apks` / `.xapk`.
+
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+
+use crate::adb::{self, run_command, RestoreOptions, RestoreReport};
+use crate::archive;
+use crate::backup_manifest;
+use crate::splits;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BundleFormat {
+    Apks,
+    Xapk,
+    Apkm,
+}
+
+impl BundleFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "apks" => Some(BundleFormat::Apks),
+            "xapk" => Some(BundleFormat::Xapk),
+            "apkm" => Some(BundleFormat::Apkm),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            BundleFormat::Apks => "apks",
+            BundleFormat::Xapk => "xapk",
+            BundleFormat::Apkm => "apkm",
+        }
+    }
+}
+
+/// XAPK OBB entry (`manifest.json` -> "expansions")
+#[derive(Serialize, Debug, Clone)]
+pub struct ObbExpansion {
+    /// Entry name inside the bundle
+    pub file: String,
+    /// Target path relative to /sdcard (e.g. "Android/obb/<pkg>/main.1.<pkg>.obb")
+    pub install_path: String,
+}
+
+/// Bundle içeriği (import öncesi önizleme)
+#[derive(Serialize, Debug, Clone)]
+pub struct BundleInfo {
+    pub format: BundleFormat,
+    pub package_name: Option<String>,
+    pub version_name: Option<String>,
+    pub version_code: Option<u64>,
+    pub apk_files: Vec<String>,
+    pub expansions: Vec<ObbExpansion>,
+}
+
+fn read_json_entry<R: Read + std::io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    name: &str,
+) -> Option<serde_json::Value> {
+    let mut entry = archive.by_name(name).ok()?;
+    let mut content = String::new();
+    entry.read_to_string(&mut content).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Version codes are strings in XAPK / APKM manifests and numbers in SAI's.
+fn json_u64(value: &serde_json::Value) -> Option<u64> {
+    value
+        .as_u64()
+        .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+}
+
+fn json_string(value: &serde_json::Value) -> Option<String> {
+    value
+        .as_str()
+        .map(|s| s.to_string())
+        .filter(|s| !s.is_empty())
+}
+
+fn open_bundle(path: &Path) -> Result<zip::ZipArchive<File>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open bundle: {}", e))?;
+    zip::ZipArchive::new(file).map_err(|e| {
+        format!(
+            "Failed to read bundle (encrypted .apkm files are not supported): {}",
+            e
+        )
+    })
+}
+
+/// Lists the APKs, package info and OBB expansions of a bundle without extracting it.
+pub fn inspect_bundle(path: &Path) -> Result<BundleInfo, String> {
+    let format = BundleFormat::from_path(path)
+        .ok_or("Unsupported bundle format (expected .apks, .xapk or .apkm)")?;
+    let mut archive = open_bundle(path)?;
+
+    let mut apk_files: Vec<String> = archive
+        .file_names()
+        .filter(|n| n.to_ascii_lowercase().ends_with(".apk") && !n.starts_with("Android/"))
+        .map(|n| n.to_string())
+        .collect();
+    apk_files.sort();
+    if apk_files.is_empty() {
+        return Err("Bundle contains no APK files".to_string());
+    }
+
+    let mut info = BundleInfo {
+        format,
+        package_name: None,
+        version_name: None,
+        version_code: None,
+        apk_files,
+        expansions: Vec::new(),
+    };
+
+    match format {
+        BundleFormat::Xapk => {
+            if let Some(manifest) = read_json_entry(&mut archive, "manifest.json") {
+                info.package_name = json_string(&manifest["package_name"]);
+                info.version_name = json_string(&manifest["version_name"]);
+                info.version_code = json_u64(&manifest["version_code"]);
+                if let Some(expansions) = manifest["expansions"].as_array() {
+                    for exp in expansions {
+                        if let (Some(file), Some(install_path)) =
+                            (exp["file"].as_str(), exp["install_path"].as_str())
+                        {
+                            info.expansions.push(ObbExpansion {
+                                file: file.to_string(),
+                                install_path: install_path.trim_start_matches('/').to_string(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        BundleFormat::Apkm => {
+            if let Some(meta) = read_json_entry(&mut archive, "info.json") {
+                info.package_name = json_string(&meta["pname"]);
+                info.version_name = json_string(&meta["release_version"]);
+                info.version_code = json_u64(&meta["versioncode"]);
+            }
+        }
+        BundleFormat::Apks => {
+            let meta = read_json_entry(&mut archive, "meta.sai_v2.json")
+                .or_else(|| read_json_entry(&mut archive, "meta.sai_v1.json"));
+            if let Some(meta) = meta {
+                info.package_name = json_string(&meta["package"]);
+                info.version_name = json_string(&meta["version_name"]);
+                info.version_code = json_u64(&meta["version_code"]);
+            }
+        }
+    }
+
+    Ok(info)
+}
+
+/// Extracts a single bundle entry to `dest`.
+fn extract_entry(
+    archive: &mut zip::ZipArchive<File>,
+    name: &str,
+    dest: &Path,
+) -> Result<(), String> {
+    let mut entry = archive
+        .by_name(name)
+        .map_err(|e| format!("Missing entry {}: {}", name, e))?;
+    archive::safe_entry_path(&entry)?;
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let mut out = File::create(dest).map_err(|e| e.to_string())?;
+    std::io::copy(&mut entry, &mut out)
+        .map_err(|e| format!("Failed to extract {}: {}", name, e))?;
+    Ok(())
+}
+
+/// Installs a `.apks` / `.xapk` / `.apkm` bundle, including XAPK OBB expansions.
+pub fn import_bundle(
+    device_id: &str,
+    bundle_path: &Path,
+    options: &RestoreOptions,
+) -> Result<RestoreReport, String> {
+    let info = inspect_bundle(bundle_path)?;
+    let mut archive = open_bundle(bundle_path)?;
+
+    let stem = bundle_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("bundle");
+    let temp_dir = env::temp_dir().join("easyadb_import").join(stem);
+    if temp_dir.exists() {
+        fs::remove_dir_all(&temp_dir).map_err(|e| e.to_string())?;
+    }
+    fs::create_dir_all(&temp_dir).map_err(|e| e.to_string())?;
+
+    let result = install_extracted(device_id, &info, &mut archive, &temp_dir, options);
+    let _ = fs::remove_dir_all(&temp_dir);
+    result
+}
+
+fn install_extracted(
+    device_id: &str,
+    info: &BundleInfo,
+    archive: &mut zip::ZipArchive<File>,
+    temp_dir: &Path,
+    options: &RestoreOptions,
+) -> Result<RestoreReport, String> {
+    let package_name = info.package_name.clone().unwrap_or_default();
+    let mut report = RestoreReport {
+        package_name: package_name.clone(),
+        message: String::new(),
+        applied: Vec::new(),
+        skipped: Vec::new(),
+        permission_failures: Vec::new(),
+    };
+
+    // Step 1: Install APKs
+    if options.components.apk {
+        let apks_dir = temp_dir.join("apks");
+        let mut apk_paths = Vec::new();
+        for name in &info.apk_files {
+            // Flatten nested entries ("splits/config.en.apk" -> "config.en.apk")
+            let file_name = Path::new(name)
+                .file_name()
+                .ok_or_else(|| format!("Invalid entry name: {}", name))?;
+            let dest = apks_dir.join(file_name);
+            extract_entry(archive, name, &dest)?;
+            apk_paths.push(dest);
+        }
+
+        if !options.install_all_splits {
+            let selection = splits::select_for_device(device_id, &apk_paths)?;
+            if !selection.excluded.is_empty() {
+                report
+                    .skipped
+                    .push(format!("splits: {}", selection.excluded.join(", ")));
+            }
+            apk_paths = selection.selected;
+        }
+
+        let install_output = adb::install_apks(device_id, &apk_paths, options)?;
+        if !install_output.contains("Success") {
+            return Err(format!("Install Failed: {}", install_output));
+        }
+        report.applied.push("apk".to_string());
+    } else {
+        report.skipped.push("apk".to_string());
+    }
+
+    // Step 2: XAPK OBB expansions
+    if !info.expansions.is_empty() {
+        if options.components.obb {
+            let obb_dir = temp_dir.join("obb");
+            for (i, exp) in info.expansions.iter().enumerate() {
+                if !is_safe_obb_path(&exp.install_path, &package_name) {
+                    return Err(format!("Unsafe OBB install path: {}", exp.install_path));
+                }
+                let local = obb_dir.join(i.to_string());
+                extract_entry(archive, &exp.file, &local)?;
+
+                let remote = format!("/sdcard/{}", exp.install_path);
+                if let Some((remote_parent, _)) = remote.rsplit_once('/') {
+                    let mkdir = format!("mkdir -p '{}'", remote_parent);
+                    run_command(&["-s", device_id, "shell", &mkdir])?;
+                }
+                run_command(&["-s", device_id, "push", &local.to_string_lossy(), &remote])?;
+                let _ = fs::remove_file(&local);
+            }
+            report.applied.push("obb".to_string());
+        } else {
+            report.skipped.push("obb".to_string());
+        }
+    }
+
+    report.message = format!("Imported: {}", report.applied.join(", "));
+    Ok(report)
+}
+
+/// `install_path` comes from the bundle's manifest and ends up in a shell
+/// command, so it must stay inside the app's own OBB folder and use plain
+/// path characters only.
+fn is_safe_obb_path(install_path: &str, package_name: &str) -> bool {
+    let prefix = format!("Android/obb/{}/", package_name);
+    !package_name.is_empty()
+        && install_path.len() > prefix.len()
+        && install_path.starts_with(&prefix)
+        && install_path
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '/' | '-'))
+        && install_path
+            .split('/')
+            .all(|p| !p.is_empty() && p != "." && p != "..")
+}
+
+/// XAPK split id from a file name: "base.apk" -> "base",
+/// "split_config.arm64_v8a.apk" -> "config.arm64_v8a".
+fn xapk_split_id(file_name: &str) -> String {
+    let stem = file_name.trim_end_matches(".apk");
+    stem.strip_prefix("split_").unwrap_or(stem).to_string()
+}
+
+/// Exports an `.easybckp` backup to `.apks` (APKs only) or `.xapk` (APKs + OBB).
+/// App data is not part of either format and is left out.
+pub fn export_bundle(
+    backup_path: &Path,
+    format: BundleFormat,
+    dest_dir: &Path,
+) -> Result<PathBuf, String> {
+    if format == BundleFormat::Apkm {
+        return Err("Export to .apkm is not supported".to_string());
+    }
+
+    let manifest = backup_manifest::read_backup_manifest(backup_path)?;
+    let file = File::open(backup_path).map_err(|e| format!("Failed to open backup: {}", e))?;
+    let mut source =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read zip: {}", e))?;
+
+    let backup_stem = backup_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("backup")
+        .to_string();
+    let package_name = manifest
+        .package()
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| backup_stem.clone());
+
+    // (entry index, name in the bundle)
+    let mut apks = Vec::new();
+    let mut obbs = Vec::new();
+    for i in 0..source.len() {
+        let entry = source.by_index_raw(i).map_err(|e| e.to_string())?;
+        let name = entry.name().to_string();
+        if entry.is_dir() {
+            continue;
+        }
+        if let Some(apk) = name.strip_prefix("apks/") {
+            apks.push((i, apk.to_string(), entry.size()));
+        } else if name == "base.apk" {
+            // Legacy root format
+            apks.push((i, name.clone(), entry.size()));
+        } else if let Some(rel) = name.strip_prefix("obb/") {
+            if format == BundleFormat::Xapk {
+                obbs.push((
+                    i,
+                    format!("Android/obb/{}/{}", package_name, rel),
+                    entry.size(),
+                ));
+            }
+        }
+    }
+    if apks.is_empty() {
+        return Err("Backup contains no APKs".to_string());
+    }
+
+    fs::create_dir_all(dest_dir).map_err(|e| e.to_string())?;
+    let dest_path = dest_dir.join(format!("{}.{}", backup_stem, format.extension()));
+    let out = File::create(&dest_path).map_err(|e| e.to_string())?;
+    let mut zip = zip::ZipWriter::new(out);
+    let options = FileOptions::<()>::default().compression_method(zip::CompressionMethod::Stored);
+
+    for (index, name, _) in apks.iter().chain(obbs.iter()) {
+        let entry = source.by_index_raw(*index).map_err(|e| e.to_string())?;
+        zip.raw_copy_file_rename(entry, name)
+            .map_err(|e| e.to_string())?;
+    }
+
+    let app = &manifest.app;
+    let version_name = app.version_name.as_deref().unwrap_or("");
+    let version_code = app.version_code.unwrap_or(0);
+
+    let (meta_name, meta) = match format {
+        BundleFormat::Xapk => {
+            let split_apks: Vec<serde_json::Value> = apks
+                .iter()
+                .map(|(_, name, _)| serde_json::json!({ "file": name, "id": xapk_split_id(name) }))
+                .collect();
+            let split_configs: Vec<String> = apks
+                .iter()
+                .map(|(_, name, _)| xapk_split_id(name))
+                .filter(|id| id != "base")
+                .collect();
+            let expansions: Vec<serde_json::Value> = obbs
+                .iter()
+                .map(|(_, name, _)| {
+                    serde_json::json!({
+                        "file": name,
+                        "install_location": "EXTERNAL_STORAGE",
+                        "install_path": name
+                    })
+                })
+                .collect();
+            let total_size: u64 = apks.iter().chain(obbs.iter()).map(|(_, _, s)| s).sum();
+
+            (
+                "manifest.json",
+                serde_json::json!({
+                    "xapk_version": 2,
+                    "package_name": package_name,
+                    "name": package_name,
+                    "version_code": version_code.to_string(),
+                    "version_name": version_name,
+                    "min_sdk_version": app.min_sdk.unwrap_or(1).to_string(),
+                    "target_sdk_version": app.target_sdk.unwrap_or(1).to_string(),
+                    "permissions": app.requested_permissions,
+                    "split_configs": split_configs,
+                    "total_size": total_size,
+                    "split_apks": split_apks,
+                    "expansions": expansions
+                }),
+            )
+        }
+        _ => (
+            "meta.sai_v2.json",
+            serde_json::json!({
+                "meta_version": 2,
+                "package": package_name,
+                "label": package_name,
+                "version_code": version_code,
+                "version_name": version_name,
+                "min_sdk": app.min_sdk,
+                "target_sdk": app.target_sdk,
+                "split_apk": apks.len() > 1,
+                "export_timestamp": chrono::Local::now().timestamp_millis()
+            }),
+        ),
+    };
+
+    zip.start_file(meta_name, options)
+        .map_err(|e| e.to_string())?;
+    zip.write_all(meta.to_string().as_bytes())
+        .map_err(|e| e.to_string())?;
+    zip.finish().map_err(|e| e.to_string())?;
+
+    Ok(dest_path)
+}
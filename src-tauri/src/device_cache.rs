@@ -14,6 +14,31 @@ pub struct DeviceProfile {
     pub model: Option<String>,
     pub last_sync: String,
     pub packages: Vec<CachedPackage>,
+    /// Son sağlam kullanıcı uygulaması seti. Bir sync cihazın silindiğini
+    /// gösteriyorsa (bilinen uygulamaların yarısından fazlası yok) korunur.
+    #[serde(default)]
+    pub known_apps: Vec<String>,
+    #[serde(default)]
+    pub known_apps_date: Option<String>,
+}
+
+/// User apps that count towards the device's app set
+fn user_apps(packages: &[CachedPackage]) -> Vec<String> {
+    let mut apps: Vec<String> = packages
+        .iter()
+        .filter(|p| !p.is_system && !p.is_uninstalled)
+        .map(|p| p.name.clone())
+        .collect();
+    apps.sort();
+    apps.dedup();
+    apps
+}
+
+/// A list that lost more than half of the known apps looks like a wiped
+/// (factory reset) device rather than a few uninstalls.
+fn looks_wiped(known: &[String], live: &[String]) -> bool {
+    let kept = known.iter().filter(|name| live.contains(name)).count();
+    kept * 2 < known.len()
 }
 
 /// Cache'lenmiş paket bilgisi
@@ -63,7 +88,12 @@ impl DeviceCache {
                 let path = entry.path();
                 if path.extension().map_or(false, |ext| ext == "json") {
                     if let Ok(content) = fs::read_to_string(&path) {
-                        if let Ok(profile) = serde_json::from_str::<DeviceProfile>(&content) {
+                        if let Ok(mut profile) = serde_json::from_str::<DeviceProfile>(&content) {
+                            // Profiles saved before `known_apps` existed
+                            if profile.known_apps.is_empty() {
+                                profile.known_apps = user_apps(&profile.packages);
+                                profile.known_apps_date = Some(profile.last_sync.clone());
+                            }
                             profiles.insert(profile.serial.clone(), profile);
                         }
                     }
@@ -83,6 +113,12 @@ impl DeviceCache {
         profiles.get(device_id).map(|p| p.packages.clone())
     }
 
+    /// Cache'teki profili getir (son senkronizasyon anındaki paket listesi)
+    pub fn get_profile(&self, device_id: &str) -> Option<DeviceProfile> {
+        let profiles = self.profiles.lock().unwrap();
+        profiles.get(device_id).cloned()
+    }
+
     /// Profili güncelle ve diske kaydet
     pub fn update_profile(
         &self,
@@ -92,18 +128,29 @@ impl DeviceCache {
     ) {
         let now = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
 
-        let profile = DeviceProfile {
-            serial: device_id.to_string(),
-            model,
-            last_sync: now,
-            packages,
-        };
+        let live_apps = user_apps(&packages);
 
         // Memory cache güncelle
-        {
+        let profile = {
             let mut profiles = self.profiles.lock().unwrap();
+            let (known_apps, known_apps_date) = match profiles.get(device_id) {
+                Some(previous) if looks_wiped(&previous.known_apps, &live_apps) => (
+                    previous.known_apps.clone(),
+                    previous.known_apps_date.clone(),
+                ),
+                _ => (live_apps, Some(now.clone())),
+            };
+            let profile = DeviceProfile {
+                serial: device_id.to_string(),
+                model,
+                last_sync: now,
+                packages,
+                known_apps,
+                known_apps_date,
+            };
             profiles.insert(device_id.to_string(), profile.clone());
-        }
+            profile
+        };
 
         // Diske yaz
         let file_path = self.cache_dir.join(format!("{}.json", device_id));
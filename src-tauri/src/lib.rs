@@ -1,11 +1,32 @@
 mod adb;
+mod adb_backup;
+mod app_set;
+mod archive;
+mod backup_catalog;
+mod backup_inspect;
+mod backup_manifest;
+mod backup_naming;
+mod backup_queue;
+mod backup_scheduler;
+mod backup_store;
+mod bundle_formats;
 mod device_cache;
+mod migration;
 mod package_db;
+mod package_info;
+mod partitions;
+mod permissions;
+mod personal_data;
+mod restore_check;
+mod restore_queue;
+mod retention;
+mod rollback;
+mod root_data;
 mod seed_data;
+mod splits;
 
 use serde::Serialize;
 use std::fs;
-use std::time::SystemTime;
 use tauri::{Emitter, Manager, State};
 
 #[derive(Clone, Serialize)]
@@ -21,6 +42,9 @@ pub struct BackupFile {
     pub path: String,
     pub size: u64,
     pub date: String,
+    pub package_name: Option<String>,
+    pub app: Option<package_info::PackageDetails>,
+    pub source_device: Option<package_info::SourceDevice>,
 }
 
 #[tauri::command]
@@ -35,9 +59,13 @@ fn list_packages(device_id: String) -> Result<Vec<adb::AppPackage>, String> {
 
 #[tauri::command]
 fn perform_backup(
+    catalog: State<'_, backup_catalog::BackupCatalog>,
+    db: State<'_, package_db::PackageDB>,
     device_id: String,
     package: adb::AppPackage,
     custom_path: Option<String>,
+    options: Option<adb::BackupOptions>,
+    name_template: Option<String>,
 ) -> Result<String, String> {
     // Kullanıcı custom path verdiyse onu kullan, yoksa Downloads
     let backup_dir = if let Some(path) = custom_path {
@@ -52,11 +80,26 @@ fn perform_backup(
         dirs::download_dir().ok_or_else(|| "Could not find Downloads directory".to_string())?
     };
 
-    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-    let filename = format!("{}_{}.easybckp", package.name, timestamp);
-    let dest_path = backup_dir.join(filename);
+    let naming =
+        backup_naming::NamingContext::collect(&device_id, &package.name, db.get(&package.name));
+    let dest_path =
+        backup_naming::resolve_backup_path(&backup_dir, name_template.as_deref(), &naming)?;
 
-    adb::backup_package(&device_id, package, dest_path)
+    let result = adb::backup_package(
+        &device_id,
+        package,
+        dest_path.clone(),
+        &options.unwrap_or_default(),
+    )?;
+    catalog.ensure_folder(&backup_dir);
+    let _ = catalog.upsert(&dest_path);
+    Ok(result)
+}
+
+#[tauri::command]
+fn preview_backup_name(template: String) -> Result<String, String> {
+    let path = backup_naming::render(&template, &backup_naming::NamingContext::sample())?;
+    Ok(path.to_string_lossy().replace('\\', "/"))
 }
 
 #[tauri::command]
@@ -72,7 +115,19 @@ fn get_package_size(device_id: String, package: adb::AppPackage) -> Result<u64,
 }
 
 #[tauri::command]
-fn list_backups(custom_path: Option<String>) -> Result<Vec<BackupFile>, String> {
+fn get_external_data_size(
+    device_id: String,
+    package_name: String,
+    excludes: Option<Vec<String>>,
+) -> Result<u64, String> {
+    adb::get_external_data_size(&device_id, &package_name, &excludes.unwrap_or_default())
+}
+
+#[tauri::command]
+fn list_backups(
+    catalog: State<'_, backup_catalog::BackupCatalog>,
+    custom_path: Option<String>,
+) -> Result<Vec<BackupFile>, String> {
     // custom_path verilmişse onu kullan, yoksa Downloads klasörü
     let backup_dir = if let Some(path) = custom_path {
         let p = std::path::PathBuf::from(&path);
@@ -87,30 +142,24 @@ fn list_backups(custom_path: Option<String>) -> Result<Vec<BackupFile>, String>
 
     let mut backups = Vec::new();
 
-    if let Ok(entries) = fs::read_dir(&backup_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.is_file() {
-                if let Some(ext) = path.extension() {
-                    if ext == "easybckp" {
-                        let metadata = fs::metadata(&path).map_err(|e| e.to_string())?;
-                        let created = metadata.created().unwrap_or(SystemTime::now());
-                        let datetime: chrono::DateTime<chrono::Local> = created.into();
-
-                        backups.push(BackupFile {
-                            name: path
-                                .file_name()
-                                .unwrap_or_default()
-                                .to_string_lossy()
-                                .to_string(),
-                            path: path.to_string_lossy().to_string(),
-                            size: metadata.len(),
-                            date: datetime.format("%Y-%m-%d %H:%M:%S").to_string(),
-                        });
-                    }
-                }
-            }
-        }
+    // Served from the catalog cache; only new or modified archives are opened.
+    // Recursive: naming templates may place backups in sub folders (device/app/...)
+    for entry in catalog.list_folder(&backup_dir) {
+        // Date comes from metadata.json (real backup date), file time for legacy backups
+        let date = chrono::DateTime::parse_from_rfc3339(&entry.backup_date)
+            .map(|d| d.with_timezone(&chrono::Local))
+            .map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or(entry.backup_date);
+
+        backups.push(BackupFile {
+            name: entry.name,
+            path: entry.path,
+            size: entry.size,
+            date,
+            package_name: entry.package_name,
+            app: entry.app,
+            source_device: entry.source_device,
+        });
     }
 
     // Sort by date descending (newest first)
@@ -120,12 +169,98 @@ fn list_backups(custom_path: Option<String>) -> Result<Vec<BackupFile>, String>
 }
 
 #[tauri::command]
-fn perform_restore(device_id: String, backup_path: String) -> Result<String, String> {
+fn perform_restore(
+    device_id: String,
+    backup_path: String,
+    options: Option<adb::RestoreOptions>,
+) -> Result<adb::RestoreReport, String> {
     let path = std::path::PathBuf::from(backup_path);
     if !path.exists() {
         return Err("Backup file not found".to_string());
     }
-    adb::restore_package(&device_id, path)
+    adb::restore_package(&device_id, path, &options.unwrap_or_default())
+}
+
+#[tauri::command]
+fn plan_bulk_restore(
+    catalog: State<'_, backup_catalog::BackupCatalog>,
+    request: restore_queue::BulkRestoreRequest,
+) -> Vec<restore_queue::PlannedRestore> {
+    restore_queue::plan(&catalog, &request)
+}
+
+#[tauri::command]
+fn start_bulk_restore(
+    app: tauri::AppHandle,
+    request: restore_queue::BulkRestoreRequest,
+) -> Result<String, String> {
+    restore_queue::start(&app, request)
+}
+
+#[tauri::command]
+fn get_bulk_restore(
+    queue: State<'_, restore_queue::RestoreQueue>,
+    job_id: String,
+) -> Result<restore_queue::BulkRestoreJob, String> {
+    queue
+        .get(&job_id)
+        .ok_or_else(|| format!("Restore job not found: {}", job_id))
+}
+
+#[tauri::command]
+fn cancel_bulk_restore(queue: State<'_, restore_queue::RestoreQueue>, job_id: String) -> bool {
+    queue.cancel(&job_id)
+}
+
+// =====================================================================
+// DEVICE MIGRATION
+// =====================================================================
+
+#[tauri::command]
+async fn list_migration_candidates(
+    app: tauri::AppHandle,
+    source: String,
+) -> Result<Vec<migration::MigrationCandidate>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let db = app.state::<package_db::PackageDB>();
+        migration::list_candidates(&source, |name| db.get(name))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn migrate_device(
+    app: tauri::AppHandle,
+    source: String,
+    target: String,
+    packages: Vec<String>,
+    options: Option<migration::MigrationOptions>,
+) -> Result<migration::MigrationReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        migration::migrate(
+            &app,
+            &source,
+            &target,
+            &packages,
+            &options.unwrap_or_default(),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn preflight_restore(
+    device_id: String,
+    backup_path: String,
+    options: Option<adb::RestoreOptions>,
+) -> Result<restore_check::RestorePreflight, String> {
+    restore_check::preflight_restore(
+        &device_id,
+        std::path::Path::new(&backup_path),
+        &options.unwrap_or_default(),
+    )
 }
 
 #[tauri::command]
@@ -173,13 +308,326 @@ fn reinstall_pkg(device_id: String, package_name: String) -> Result<String, Stri
 }
 
 #[tauri::command]
-fn delete_backup(path: String) -> Result<(), String> {
-    let path = std::path::PathBuf::from(path);
+fn delete_backup(
+    catalog: State<'_, backup_catalog::BackupCatalog>,
+    path: String,
+) -> Result<(), String> {
+    catalog.delete_backup(std::path::Path::new(&path))
+}
+
+// =====================================================================
+// RETENTION (Automatic Pruning)
+// =====================================================================
+
+#[tauri::command]
+fn get_retention_policy(
+    policies: State<'_, retention::RetentionPolicies>,
+) -> retention::RetentionPolicy {
+    policies.get()
+}
+
+#[tauri::command]
+fn set_retention_policy(
+    policies: State<'_, retention::RetentionPolicies>,
+    policy: retention::RetentionPolicy,
+) -> Result<(), String> {
+    policies.set(policy)
+}
+
+#[tauri::command]
+async fn preview_retention(app: tauri::AppHandle) -> Result<retention::RetentionPlan, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let policy = app.state::<retention::RetentionPolicies>().get();
+        retention::preview(&app.state::<backup_catalog::BackupCatalog>(), &policy)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn apply_retention(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+) -> Result<retention::RetentionReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let policy = app.state::<retention::RetentionPolicies>().get();
+        retention::apply(
+            &app.state::<backup_catalog::BackupCatalog>(),
+            &policy,
+            &paths,
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// =====================================================================
+// SCHEDULED BACKUPS
+// =====================================================================
+
+#[tauri::command]
+fn list_backup_schedules(
+    scheduler: State<'_, backup_scheduler::BackupScheduler>,
+) -> Vec<backup_scheduler::BackupSchedule> {
+    scheduler.list()
+}
+
+#[tauri::command]
+fn set_backup_schedule(
+    scheduler: State<'_, backup_scheduler::BackupScheduler>,
+    schedule: backup_scheduler::BackupSchedule,
+) {
+    scheduler.set(schedule)
+}
+
+#[tauri::command]
+fn remove_backup_schedule(scheduler: State<'_, backup_scheduler::BackupScheduler>, serial: String) {
+    scheduler.remove(&serial)
+}
+
+#[tauri::command]
+fn get_backup_history(
+    scheduler: State<'_, backup_scheduler::BackupScheduler>,
+    serial: Option<String>,
+) -> Vec<backup_scheduler::ScheduleRun> {
+    scheduler.history(serial.as_deref())
+}
+
+#[tauri::command]
+async fn run_backup_schedule_now(
+    app: tauri::AppHandle,
+    serial: String,
+) -> Result<backup_scheduler::ScheduleRun, String> {
+    tauri::async_runtime::spawn_blocking(move || backup_scheduler::run_now(&app, &serial))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// =====================================================================
+// BACKUP QUEUE
+// =====================================================================
+
+#[tauri::command]
+fn enqueue_backups(
+    app: tauri::AppHandle,
+    device_id: String,
+    packages: Vec<adb::AppPackage>,
+    destination: Option<String>,
+    name_template: Option<String>,
+    options: Option<adb::BackupOptions>,
+    concurrency: Option<usize>,
+) -> Result<backup_queue::BackupJob, String> {
+    if packages.is_empty() {
+        return Err("No packages selected".to_string());
+    }
+    let job = app.state::<backup_queue::BackupQueue>().enqueue(
+        device_id,
+        packages,
+        destination,
+        name_template,
+        options.unwrap_or_default(),
+        concurrency.unwrap_or(1),
+    );
+    backup_queue::start_job(&app, &job.id, None)?;
+    Ok(job)
+}
+
+#[tauri::command]
+fn list_backup_jobs(queue: State<'_, backup_queue::BackupQueue>) -> Vec<backup_queue::BackupJob> {
+    queue.list()
+}
+
+#[tauri::command]
+fn get_backup_job_summary(
+    queue: State<'_, backup_queue::BackupQueue>,
+    job_id: String,
+) -> Result<backup_queue::JobSummary, String> {
+    queue
+        .get(&job_id)
+        .map(|job| job.summary())
+        .ok_or_else(|| format!("Backup job not found: {}", job_id))
+}
+
+#[tauri::command]
+fn cancel_backup_job(queue: State<'_, backup_queue::BackupQueue>, job_id: String) -> bool {
+    queue.cancel(&job_id)
+}
+
+#[tauri::command]
+fn resume_backup_job(
+    app: tauri::AppHandle,
+    job_id: String,
+    adb_backup_password: Option<String>,
+) -> Result<(), String> {
+    backup_queue::start_job(&app, &job_id, adb_backup_password)
+}
+
+#[tauri::command]
+fn clear_finished_backup_jobs(queue: State<'_, backup_queue::BackupQueue>) {
+    queue.clear_finished()
+}
+
+#[tauri::command]
+fn inspect_backup(backup_path: String) -> Result<backup_inspect::BackupInspection, String> {
+    backup_inspect::inspect_backup(std::path::Path::new(&backup_path))
+}
+
+#[tauri::command]
+fn extract_backup_component(
+    backup_path: String,
+    component: String,
+    dest_path: String,
+) -> Result<Vec<String>, String> {
+    let paths = backup_inspect::extract_component(
+        std::path::Path::new(&backup_path),
+        &component,
+        std::path::Path::new(&dest_path),
+    )?;
+    Ok(paths
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect())
+}
+
+// =====================================================================
+// BACKUP CATALOG (Metadata Index)
+// =====================================================================
+
+#[tauri::command]
+fn get_catalog_folders(catalog: State<'_, backup_catalog::BackupCatalog>) -> Vec<String> {
+    catalog.folders()
+}
+
+#[tauri::command]
+async fn set_catalog_folders(app: tauri::AppHandle, folders: Vec<String>) -> Result<usize, String> {
+    // Indexing reads every archive; keep it off the UI thread
+    tauri::async_runtime::spawn_blocking(move || {
+        app.state::<backup_catalog::BackupCatalog>()
+            .set_folders(folders)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn refresh_backup_catalog(app: tauri::AppHandle) -> Result<usize, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        app.state::<backup_catalog::BackupCatalog>().refresh()
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn query_backups(
+    catalog: State<'_, backup_catalog::BackupCatalog>,
+    query: Option<backup_catalog::CatalogQuery>,
+) -> Vec<backup_catalog::CatalogEntry> {
+    catalog.query(&query.unwrap_or_default())
+}
+
+// =====================================================================
+// BACKUP STORE (Deduplicating Repository)
+// =====================================================================
+
+#[tauri::command]
+fn perform_store_backup(
+    device_id: String,
+    package: adb::AppPackage,
+    store_path: String,
+    options: Option<adb::BackupOptions>,
+) -> Result<backup_store::StoreSnapshot, String> {
+    // Önce normal bir .easybckp üret, sonra store'a aktarıp geçici dosyayı sil
+    let staging_dir = std::env::temp_dir().join("easyadb_processing");
+    fs::create_dir_all(&staging_dir).map_err(|e| e.to_string())?;
+    let staging_file = staging_dir.join(format!(
+        "{}_store_{}.easybckp",
+        package.name,
+        chrono::Local::now().format("%Y%m%d_%H%M%S%3f")
+    ));
+
+    let options = options.unwrap_or_default();
+    let result =
+        adb::backup_package(&device_id, package, staging_file.clone(), &options).and_then(|_| {
+            backup_store::ingest_archive(std::path::Path::new(&store_path), &staging_file)
+        });
+
+    let _ = fs::remove_file(&staging_file);
+    result
+}
+
+#[tauri::command]
+fn list_store_snapshots(store_path: String) -> Result<Vec<backup_store::StoreSnapshot>, String> {
+    backup_store::list_snapshots(std::path::Path::new(&store_path))
+}
+
+#[tauri::command]
+fn delete_store_snapshot(
+    store_path: String,
+    snapshot_id: String,
+) -> Result<backup_store::GcReport, String> {
+    backup_store::delete_snapshot(std::path::Path::new(&store_path), &snapshot_id)
+}
+
+#[tauri::command]
+fn gc_backup_store(store_path: String) -> Result<backup_store::GcReport, String> {
+    backup_store::collect_garbage(std::path::Path::new(&store_path))
+}
+
+#[tauri::command]
+fn export_store_snapshot(
+    store_path: String,
+    snapshot_id: String,
+    dest_path: Option<String>,
+) -> Result<String, String> {
+    let dest_dir = match dest_path {
+        Some(p) => std::path::PathBuf::from(p),
+        None => {
+            dirs::download_dir().ok_or_else(|| "Could not find Downloads directory".to_string())?
+        }
+    };
+    let path =
+        backup_store::export_snapshot(std::path::Path::new(&store_path), &snapshot_id, &dest_dir)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+// =====================================================================
+// APK BUNDLES (.apks / .xapk / .apkm)
+// =====================================================================
+
+#[tauri::command]
+fn inspect_bundle(bundle_path: String) -> Result<bundle_formats::BundleInfo, String> {
+    bundle_formats::inspect_bundle(std::path::Path::new(&bundle_path))
+}
+
+#[tauri::command]
+fn import_bundle(
+    device_id: String,
+    bundle_path: String,
+    options: Option<adb::RestoreOptions>,
+) -> Result<adb::RestoreReport, String> {
+    let path = std::path::PathBuf::from(bundle_path);
     if !path.exists() {
-        return Err("Backup file not found".to_string());
+        return Err("Bundle file not found".to_string());
     }
-    fs::remove_file(path).map_err(|e| format!("Failed to delete backup: {}", e))?;
-    Ok(())
+    bundle_formats::import_bundle(&device_id, &path, &options.unwrap_or_default())
+}
+
+#[tauri::command]
+fn export_backup_bundle(
+    backup_path: String,
+    format: bundle_formats::BundleFormat,
+    dest_path: Option<String>,
+) -> Result<String, String> {
+    let dest_dir = match dest_path {
+        Some(p) => std::path::PathBuf::from(p),
+        None => {
+            dirs::download_dir().ok_or_else(|| "Could not find Downloads directory".to_string())?
+        }
+    };
+    let path =
+        bundle_formats::export_bundle(std::path::Path::new(&backup_path), format, &dest_dir)?;
+    Ok(path.to_string_lossy().to_string())
 }
 
 #[tauri::command]
@@ -242,6 +690,19 @@ fn sync_backup_packages(
     state.inner().sync_backup_packages(&device_id)
 }
 
+#[tauri::command]
+async fn restore_device_app_set(
+    app: tauri::AppHandle,
+    device_id: String,
+    dry_run: Option<bool>,
+) -> Result<app_set::AppSetPlan, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        app_set::restore_app_set(&app, &device_id, dry_run.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
 // =====================================================================
 // PACKAGE LABEL RESOLUTION (With Self-Learning Database)
 // =====================================================================
@@ -301,6 +762,84 @@ async fn resolve_package_label(
     Ok(None)
 }
 
+// =====================================================================
+// PARTITION BACKUP (Root + Fastboot)
+// =====================================================================
+
+#[tauri::command]
+async fn list_partitions(device_id: String) -> Result<Vec<partitions::PartitionInfo>, String> {
+    tauri::async_runtime::spawn_blocking(move || partitions::list_critical(&device_id))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn backup_partitions(
+    app: tauri::AppHandle,
+    device_id: String,
+    partitions: Option<Vec<String>>,
+    dest_dir: Option<String>,
+) -> Result<partitions::PartitionBackupReport, String> {
+    let dest_root = match dest_dir {
+        Some(dir) if !dir.is_empty() => std::path::PathBuf::from(dir),
+        _ => dirs::download_dir().ok_or("Could not find Downloads directory")?,
+    };
+    let names = partitions.unwrap_or_else(|| {
+        partitions::CRITICAL_PARTITIONS
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    });
+
+    tauri::async_runtime::spawn_blocking(move || {
+        partitions::backup(&device_id, &names, &dest_root, |progress| {
+            let _ = app.emit("partition-backup-progress", progress);
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn flash_partition_image(
+    image_path: String,
+    serial: Option<String>,
+    partition: Option<String>,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        partitions::flash_image(
+            std::path::Path::new(&image_path),
+            serial.as_deref(),
+            partition.as_deref(),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// =====================================================================
+// PERSONAL DATA EXPORT (Contacts / SMS / Call log)
+// =====================================================================
+
+#[tauri::command]
+async fn export_personal_data(
+    device_id: String,
+    selection: Option<personal_data::PersonalDataSelection>,
+    dest_dir: Option<String>,
+) -> Result<personal_data::PersonalDataReport, String> {
+    let dest_root = match dest_dir {
+        Some(dir) if !dir.is_empty() => std::path::PathBuf::from(dir),
+        _ => dirs::download_dir().ok_or("Could not find Downloads directory")?,
+    };
+    let selection = selection.unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        personal_data::export(&device_id, &selection, &dest_root)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
 // =====================================================================
 // DEVICE INTEGRITY CHECK
 // =====================================================================
@@ -442,8 +981,16 @@ pub fn run() {
             list_packages,
             perform_backup,
             get_package_size,
+            get_external_data_size,
             list_backups,
             perform_restore,
+            preflight_restore,
+            plan_bulk_restore,
+            start_bulk_restore,
+            get_bulk_restore,
+            cancel_bulk_restore,
+            list_migration_candidates,
+            migrate_device,
             run_adb_command,
             check_tools,
             install_tools,
@@ -459,8 +1006,43 @@ pub fn run() {
             get_cached_packages,
             sync_device_packages,
             sync_backup_packages,
+            restore_device_app_set,
             get_default_backup_path,
-            check_device_integrity
+            preview_backup_name,
+            check_device_integrity,
+            list_partitions,
+            backup_partitions,
+            flash_partition_image,
+            export_personal_data,
+            perform_store_backup,
+            list_store_snapshots,
+            delete_store_snapshot,
+            gc_backup_store,
+            export_store_snapshot,
+            inspect_bundle,
+            import_bundle,
+            export_backup_bundle,
+            get_catalog_folders,
+            set_catalog_folders,
+            refresh_backup_catalog,
+            query_backups,
+            inspect_backup,
+            extract_backup_component,
+            get_retention_policy,
+            set_retention_policy,
+            preview_retention,
+            apply_retention,
+            list_backup_schedules,
+            set_backup_schedule,
+            remove_backup_schedule,
+            get_backup_history,
+            run_backup_schedule_now,
+            enqueue_backups,
+            list_backup_jobs,
+            get_backup_job_summary,
+            cancel_backup_job,
+            resume_backup_job,
+            clear_finished_backup_jobs
         ])
         .setup(|app| {
             // Initialize PackageDB
@@ -471,6 +1053,25 @@ pub fn run() {
             let device_cache = device_cache::DeviceCache::new(app.handle());
             app.manage(device_cache);
 
+            // Initialize BackupCatalog
+            let catalog = backup_catalog::BackupCatalog::new(app.handle());
+            app.manage(catalog);
+
+            // Initialize RetentionPolicies
+            let retention_policies = retention::RetentionPolicies::new(app.handle());
+            app.manage(retention_policies);
+
+            // Initialize BackupScheduler
+            let scheduler = backup_scheduler::BackupScheduler::new(app.handle());
+            app.manage(scheduler);
+
+            // Initialize BackupQueue (restores unfinished jobs)
+            let backup_queue = backup_queue::BackupQueue::new(app.handle());
+            app.manage(backup_queue);
+
+            // Initialize RestoreQueue (bulk restore jobs, in memory)
+            app.manage(restore_queue::RestoreQueue::new());
+
             match adb::init() {
                 Ok(path) => {
                     log::info!("ADB initialized at: {:?}", path);
@@ -479,6 +1080,11 @@ pub fn run() {
                     log::error!("Failed to initialize ADB: {}", e);
                 }
             }
+
+            // Watch for devices with backup schedules (needs ADB)
+            backup_scheduler::start(app.handle().clone());
+            // Resume queued backups when their device is back
+            backup_queue::start(app.handle().clone());
             Ok(())
         })
         .run(tauri::generate_context!())
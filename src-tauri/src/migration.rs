@@ -0,0 +1,228 @@
+//! Device-to-device migration.
+//!
+//! Each selected app is backed up from the source into a throwaway
+//! `.easybckp` in the temp folder and restored onto the target right away,
+//! through the regular `backup_package` / `restore_package` paths. The temp
+//! archive is deleted after every app, so no backup is left behind. App data
+//! is only moved when both devices are rooted.
+
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use tauri::{AppHandle, Emitter};
+
+use crate::adb::{self, run_command, BackupOptions, RestoreOptions, RestoreReport};
+use crate::backup_naming;
+use crate::root_data;
+
+/// Taşınabilecek kullanıcı uygulaması
+#[derive(Serialize, Debug, Clone)]
+pub struct MigrationCandidate {
+    pub package: adb::AppPackage,
+    pub label: Option<String>,
+    /// Base + split APKs in bytes
+    pub apk_size: u64,
+    /// App data dirs (CE + DE) in bytes; `None` without root
+    pub data_size: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct MigrationOptions {
+    pub include_obb: bool,
+    /// Root on both devices required; ignored otherwise
+    pub include_data: bool,
+    pub include_external_data: bool,
+    pub include_permissions: bool,
+}
+
+impl Default for MigrationOptions {
+    fn default() -> Self {
+        MigrationOptions {
+            include_obb: true,
+            include_data: true,
+            include_external_data: false,
+            include_permissions: true,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MigrationResult {
+    pub package_name: String,
+    pub success: bool,
+    pub message: String,
+    pub report: Option<RestoreReport>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MigrationReport {
+    pub source: String,
+    pub target: String,
+    /// Whether app data was part of the migration (both devices rooted)
+    pub data_migrated: bool,
+    pub results: Vec<MigrationResult>,
+}
+
+#[derive(Serialize, Clone)]
+struct MigrationProgress {
+    package_name: String,
+    /// "backup" or "restore"
+    stage: String,
+    current: usize,
+    total: usize,
+}
+
+/// Sums every APK next to the base APK (splits included).
+fn apks_size(device_id: &str, package: &adb::AppPackage) -> u64 {
+    let base_size = || adb::get_package_size(device_id, &package.path).unwrap_or(0);
+    let Some((dir, _)) = package.path.rsplit_once('/') else {
+        return base_size();
+    };
+    let stat_cmd = format!("stat -c %s '{}'/*.apk", dir);
+    let total: u64 = run_command(&["-s", device_id, "shell", &stat_cmd])
+        .map(|out| {
+            out.lines()
+                .filter_map(|l| l.trim().parse::<u64>().ok())
+                .sum()
+        })
+        .unwrap_or(0);
+    if total > 0 {
+        total
+    } else {
+        base_size()
+    }
+}
+
+/// CE + DE data dirs of user 0, through root.
+fn data_size(device_id: &str, package_name: &str) -> Option<u64> {
+    let du_cmd = format!(
+        "du -sk {} {} 2>/dev/null; true",
+        root_data::ce_dir(0, package_name),
+        root_data::de_dir(0, package_name)
+    );
+    let out = run_command(&["-s", device_id, "shell", "su", "-c", &du_cmd]).ok()?;
+    let kb: Vec<u64> = out
+        .lines()
+        .filter_map(|l| l.split_whitespace().next()?.parse().ok())
+        .collect();
+    (!kb.is_empty()).then(|| kb.iter().sum::<u64>() * 1024)
+}
+
+/// User apps on the source device with their APK and data sizes.
+pub fn list_candidates(
+    source: &str,
+    label_of: impl Fn(&str) -> Option<String>,
+) -> Result<Vec<MigrationCandidate>, String> {
+    let rooted = adb::is_device_rooted(source);
+    let mut candidates: Vec<MigrationCandidate> = adb::get_packages(source)?
+        .into_iter()
+        .filter(|p| !p.is_system)
+        .map(|package| MigrationCandidate {
+            apk_size: apks_size(source, &package),
+            data_size: if rooted {
+                data_size(source, &package.name)
+            } else {
+                None
+            },
+            label: label_of(&package.name),
+            package,
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.package.name.cmp(&b.package.name));
+    Ok(candidates)
+}
+
+/// Moves `packages` from `source` to `target`, one app at a time.
+pub fn migrate(
+    app: &AppHandle,
+    source: &str,
+    target: &str,
+    packages: &[String],
+    options: &MigrationOptions,
+) -> Result<MigrationReport, String> {
+    if source == target {
+        return Err("Source and target must be different devices".to_string());
+    }
+
+    let installed = adb::get_packages(source)?;
+    let data_migrated =
+        options.include_data && adb::is_device_rooted(source) && adb::is_device_rooted(target);
+
+    let backup_options = BackupOptions {
+        include_obb: options.include_obb,
+        include_data: data_migrated,
+        include_external_data: options.include_external_data,
+        ..Default::default()
+    };
+    let mut restore_options = RestoreOptions::default();
+    restore_options.components.obb = options.include_obb;
+    restore_options.components.data = data_migrated;
+    restore_options.components.external_data = options.include_external_data;
+    restore_options.components.permissions = options.include_permissions;
+
+    let temp_dir = env::temp_dir().join("easyadb_migration").join(format!(
+        "{}_to_{}",
+        backup_naming::sanitize_component(source),
+        backup_naming::sanitize_component(target)
+    ));
+    fs::create_dir_all(&temp_dir).map_err(|e| e.to_string())?;
+
+    let mut report = MigrationReport {
+        source: source.to_string(),
+        target: target.to_string(),
+        data_migrated,
+        results: Vec::new(),
+    };
+
+    for (i, package_name) in packages.iter().enumerate() {
+        let progress = |stage: &str| {
+            let _ = app.emit(
+                "migration-progress",
+                MigrationProgress {
+                    package_name: package_name.clone(),
+                    stage: stage.to_string(),
+                    current: i + 1,
+                    total: packages.len(),
+                },
+            );
+        };
+
+        let Some(package) = installed.iter().find(|p| &p.name == package_name) else {
+            report.results.push(MigrationResult {
+                package_name: package_name.clone(),
+                success: false,
+                message: "Not installed on the source device".to_string(),
+                report: None,
+            });
+            continue;
+        };
+
+        progress("backup");
+        let archive = temp_dir.join(format!("{}.easybckp", package_name));
+        let result = adb::backup_package(source, package.clone(), archive.clone(), &backup_options)
+            .and_then(|_| {
+                progress("restore");
+                adb::restore_package(target, archive.clone(), &restore_options)
+            });
+        let _ = fs::remove_file(&archive);
+
+        report.results.push(match result {
+            Ok(restore_report) => MigrationResult {
+                package_name: package_name.clone(),
+                success: true,
+                message: restore_report.message.clone(),
+                report: Some(restore_report),
+            },
+            Err(e) => MigrationResult {
+                package_name: package_name.clone(),
+                success: false,
+                message: e,
+                report: None,
+            },
+        });
+    }
+
+    let _ = fs::remove_dir_all(&temp_dir);
+    Ok(report)
+}
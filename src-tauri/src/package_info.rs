@@ -0,0 +1,391 @@
+//! Rich application metadata for backups.
+//!
+//! Collects version, SDK, installer, timestamps and permissions from
+//! `dumpsys package`, and native ABIs / signing certificate from the pulled APKs.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::adb::run_command;
+
+/// Uygulama detayları (metadata.json -> "app")
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageDetails {
+    pub version_code: Option<u64>,
+    pub version_name: Option<String>,
+    pub min_sdk: Option<u32>,
+    pub target_sdk: Option<u32>,
+    pub installer_package: Option<String>,
+    pub first_install_time: Option<String>,
+    pub last_update_time: Option<String>,
+    pub signing_cert_sha256: Option<String>,
+    #[serde(default)]
+    pub requested_permissions: Vec<String>,
+    /// ABIs found under `lib/<abi>/` in the APKs. Empty means no native code.
+    #[serde(default)]
+    pub native_abis: Vec<String>,
+    /// `ALLOW_BACKUP` in the package flags; required for `adb backup`
+    pub allow_backup: Option<bool>,
+}
+
+/// Yedeğin alındığı cihaz (metadata.json -> "sourceDevice")
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceDevice {
+    /// adb serial of the device the backup was taken from
+    pub serial: Option<String>,
+    pub model: Option<String>,
+    pub manufacturer: Option<String>,
+    pub fingerprint: Option<String>,
+    pub sdk: Option<u32>,
+}
+
+pub fn get_prop(device_id: &str, prop: &str) -> Option<String> {
+    run_command(&["-s", device_id, "shell", "getprop", prop])
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+pub fn query_source_device(device_id: &str) -> SourceDevice {
+    SourceDevice {
+        serial: Some(device_id.to_string()),
+        model: get_prop(device_id, "ro.product.model"),
+        manufacturer: get_prop(device_id, "ro.product.manufacturer"),
+        fingerprint: get_prop(device_id, "ro.build.fingerprint"),
+        sdk: get_prop(device_id, "ro.build.version.sdk").and_then(|s| s.parse().ok()),
+    }
+}
+
+/// Runs `dumpsys package <pkg>` and parses the fields we care about.
+pub fn query_package_details(device_id: &str, package_name: &str) -> PackageDetails {
+    match run_command(&["-s", device_id, "shell", "dumpsys", "package", package_name]) {
+        Ok(output) => parse_dumpsys_package(&output, package_name),
+        Err(_) => PackageDetails::default(),
+    }
+}
+
+/// Returns only the `Package [<name>]` block of a dumpsys output.
+/// dumpsys also prints queries, shared users, hidden system packages etc.
+pub fn package_section<'a>(output: &'a str, package_name: &str) -> Vec<&'a str> {
+    let header = format!("Package [{}]", package_name);
+    let mut lines = Vec::new();
+    let mut inside = false;
+    let mut header_indent = 0;
+
+    for line in output.lines() {
+        let indent = line.len() - line.trim_start().len();
+        if !inside {
+            if line.trim_start().starts_with(&header) {
+                inside = true;
+                header_indent = indent;
+            }
+            continue;
+        }
+        if !line.trim().is_empty() && indent <= header_indent {
+            break;
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Parses `key=value` tokens on a line such as
+/// `versionCode=231578004 minSdk=21 targetSdk=33`.
+fn line_values(line: &str) -> impl Iterator<Item = (&str, &str)> {
+    line.split_whitespace().filter_map(|t| t.split_once('='))
+}
+
+pub fn parse_dumpsys_package(output: &str, package_name: &str) -> PackageDetails {
+    let mut details = PackageDetails::default();
+    let section = package_section(output, package_name);
+
+    let mut in_requested = false;
+    let mut requested_indent = 0;
+
+    for line in &section {
+        let trimmed = line.trim();
+        let indent = line.len() - line.trim_start().len();
+
+        if in_requested {
+            if indent > requested_indent && !trimmed.ends_with(':') {
+                // "android.permission.CAMERA: restricted=true" -> "android.permission.CAMERA"
+                let perm = trimmed.split(':').next().unwrap_or(trimmed).trim();
+                if !perm.is_empty() {
+                    details.requested_permissions.push(perm.to_string());
+                }
+                continue;
+            }
+            in_requested = false;
+        }
+
+        if trimmed == "requested permissions:" {
+            in_requested = true;
+            requested_indent = indent;
+            continue;
+        }
+
+        // flags=[ HAS_CODE ALLOW_CLEAR_USER_DATA ALLOW_BACKUP ]
+        if let Some(v) = trimmed.strip_prefix("flags=[") {
+            details
+                .allow_backup
+                .get_or_insert_with(|| v.split_whitespace().any(|f| f == "ALLOW_BACKUP"));
+            continue;
+        }
+
+        // Values containing spaces (versionName, dates) are taken as the rest of the line
+        if let Some(v) = trimmed.strip_prefix("versionName=") {
+            details.version_name.get_or_insert_with(|| v.to_string());
+            continue;
+        }
+        if let Some(v) = trimmed.strip_prefix("lastUpdateTime=") {
+            details
+                .last_update_time
+                .get_or_insert_with(|| v.to_string());
+            continue;
+        }
+        if let Some(v) = trimmed.strip_prefix("firstInstallTime=") {
+            details
+                .first_install_time
+                .get_or_insert_with(|| v.to_string());
+            continue;
+        }
+
+        for (key, value) in line_values(trimmed) {
+            match key {
+                "versionCode" if details.version_code.is_none() => {
+                    details.version_code = value.parse().ok()
+                }
+                "minSdk" if details.min_sdk.is_none() => details.min_sdk = value.parse().ok(),
+                "targetSdk" if details.target_sdk.is_none() => {
+                    details.target_sdk = value.parse().ok()
+                }
+                "installerPackageName"
+                    if details.installer_package.is_none() && value != "null" =>
+                {
+                    details.installer_package = Some(value.to_string())
+                }
+                _ => {}
+            }
+        }
+    }
+
+    details
+}
+
+/// Collects native library ABIs and the signing certificate digest from pulled APKs.
+pub fn inspect_apks(apk_paths: &[PathBuf]) -> (Vec<String>, Option<String>) {
+    let mut abis = BTreeSet::new();
+    let mut cert = None;
+
+    for path in apk_paths {
+        abis.extend(native_abis(path));
+
+        // All splits are signed with the same key; base.apk is enough
+        if cert.is_none() || path.file_name().is_some_and(|n| n == "base.apk") {
+            if let Some(c) = signing_cert_sha256(path) {
+                cert = Some(c);
+            }
+        }
+    }
+
+    (abis.into_iter().collect(), cert)
+}
+
+/// Lists `lib/<abi>/` folders inside an APK.
+pub fn native_abis(apk_path: &Path) -> BTreeSet<String> {
+    let mut abis = BTreeSet::new();
+    let archive = File::open(apk_path)
+        .ok()
+        .and_then(|f| zip::ZipArchive::new(f).ok());
+
+    if let Some(archive) = archive {
+        for name in archive.file_names() {
+            let parts: Vec<&str> = name.split('/').collect();
+            if parts.len() >= 3 && parts[0] == "lib" && !parts[1].is_empty() {
+                abis.insert(parts[1].to_string());
+            }
+        }
+    }
+    abis
+}
+
+// =====================================================================
+// SIGNING CERTIFICATE
+// =====================================================================
+
+const APK_SIG_BLOCK_MAGIC: &[u8; 16] = b"APK Sig Block 42";
+const APK_SIGNATURE_SCHEME_V2_ID: u32 = 0x7109871a;
+const APK_SIGNATURE_SCHEME_V3_ID: u32 = 0xf05368c0;
+
+/// SHA-256 of the APK's signing certificate (lowercase hex, same as `apksigner`).
+/// Tries the v3/v2 APK Signing Block first, then falls back to the v1 JAR signature.
+pub fn signing_cert_sha256(apk_path: &Path) -> Option<String> {
+    let cert = signing_block_certificate(apk_path).or_else(|| jar_certificate(apk_path))?;
+    Some(format!("{:x}", Sha256::digest(&cert)))
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Option<u32> {
+    data.get(pos..pos + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_u64(data: &[u8], pos: usize) -> Option<u64> {
+    data.get(pos..pos + 8).map(|b| {
+        let mut arr = [0u8; 8];
+        arr.copy_from_slice(b);
+        u64::from_le_bytes(arr)
+    })
+}
+
+/// Splits a sequence of uint32 length-prefixed values.
+fn length_prefixed_items(data: &[u8]) -> Vec<&[u8]> {
+    let mut items = Vec::new();
+    let mut pos = 0;
+    while let Some(len) = read_u32(data, pos) {
+        let start = pos + 4;
+        let Some(end) = usize::try_from(len)
+            .ok()
+            .and_then(|len| start.checked_add(len))
+        else {
+            break;
+        };
+        match data.get(start..end) {
+            Some(item) => items.push(item),
+            None => break,
+        }
+        pos = end;
+    }
+    items
+}
+
+fn signing_block_certificate(apk_path: &Path) -> Option<Vec<u8>> {
+    let mut file = File::open(apk_path).ok()?;
+    let file_len = file.metadata().ok()?.len();
+
+    // End of Central Directory: 22 bytes + up to 65535 bytes of comment
+    let tail_len = file_len.min(22 + 65535);
+    file.seek(SeekFrom::Start(file_len - tail_len)).ok()?;
+    let mut tail = vec![0u8; tail_len as usize];
+    file.read_exact(&mut tail).ok()?;
+
+    let eocd = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&i| tail[i..i + 4] == [0x50, 0x4b, 0x05, 0x06])?;
+    let cd_offset = read_u32(&tail, eocd + 16)? as u64;
+    if cd_offset < 24 {
+        return None;
+    }
+
+    // Footer right before the Central Directory: block size (u64) + magic
+    file.seek(SeekFrom::Start(cd_offset - 24)).ok()?;
+    let mut footer = [0u8; 24];
+    file.read_exact(&mut footer).ok()?;
+    if &footer[8..] != APK_SIG_BLOCK_MAGIC {
+        return None;
+    }
+    let block_size = read_u64(&footer, 0)?;
+    let block_start = cd_offset.checked_sub(block_size.checked_add(8)?)?;
+
+    // ID-value pairs sit between the leading size field and the footer
+    let pairs_len = usize::try_from(block_size.checked_sub(24)?).ok()?;
+    file.seek(SeekFrom::Start(block_start + 8)).ok()?;
+    let mut pairs = vec![0u8; pairs_len];
+    file.read_exact(&mut pairs).ok()?;
+
+    let mut v2 = None;
+    let mut v3 = None;
+    let mut pos = 0;
+    while let Some(len) = read_u64(&pairs, pos) {
+        // Lengths come from the file; an overflowing one means a corrupt block
+        let end = usize::try_from(len)
+            .ok()
+            .and_then(|len| (pos + 8).checked_add(len))?;
+        let id = read_u32(&pairs, pos + 8)?;
+        let value = pairs.get(pos + 12..end)?;
+        match id {
+            APK_SIGNATURE_SCHEME_V2_ID => v2 = Some(value),
+            APK_SIGNATURE_SCHEME_V3_ID => v3 = Some(value),
+            _ => {}
+        }
+        pos = end;
+    }
+
+    // v3 carries the current signer after key rotation
+    let scheme_block = v3.or(v2)?;
+
+    // signers -> signer -> signed data -> (digests, certificates)
+    let signers = length_prefixed_items(scheme_block);
+    let signer = length_prefixed_items(signers.first()?).into_iter().next()?;
+    let signed_data = length_prefixed_items(signer).into_iter().next()?;
+    let sections = length_prefixed_items(signed_data);
+    let certificates = length_prefixed_items(sections.get(1)?);
+    certificates.first().map(|c| c.to_vec())
+}
+
+/// Reads a DER TLV header. Returns (tag, content start, content length).
+fn der_header(data: &[u8], pos: usize) -> Option<(u8, usize, usize)> {
+    let tag = *data.get(pos)?;
+    let first = *data.get(pos + 1)?;
+    if first < 0x80 {
+        return Some((tag, pos + 2, first as usize));
+    }
+    let count = (first & 0x7f) as usize;
+    if count == 0 || count > 4 {
+        return None;
+    }
+    let mut len = 0usize;
+    for i in 0..count {
+        len = (len << 8) | *data.get(pos + 2 + i)? as usize;
+    }
+    Some((tag, pos + 2 + count, len))
+}
+
+/// Extracts the first certificate from `META-INF/*.RSA|DSA|EC` (PKCS#7 SignedData).
+fn jar_certificate(apk_path: &Path) -> Option<Vec<u8>> {
+    let file = File::open(apk_path).ok()?;
+    let mut archive = zip::ZipArchive::new(file).ok()?;
+
+    let sig_name = archive
+        .file_names()
+        .find(|n| {
+            let upper = n.to_uppercase();
+            upper.starts_with("META-INF/")
+                && (upper.ends_with(".RSA") || upper.ends_with(".DSA") || upper.ends_with(".EC"))
+        })?
+        .to_string();
+
+    let mut pkcs7 = Vec::new();
+    archive
+        .by_name(&sig_name)
+        .ok()?
+        .read_to_end(&mut pkcs7)
+        .ok()?;
+
+    // ContentInfo ::= SEQUENCE { contentType OID, content [0] EXPLICIT SignedData }
+    let (_, ci_start, _) = der_header(&pkcs7, 0)?;
+    let (_, oid_start, oid_len) = der_header(&pkcs7, ci_start)?;
+    let (_, explicit_start, _) = der_header(&pkcs7, oid_start + oid_len)?;
+
+    // SignedData ::= SEQUENCE { version, digestAlgorithms, contentInfo, [0] certificates, ... }
+    let (_, sd_start, _) = der_header(&pkcs7, explicit_start)?;
+    let mut pos = sd_start;
+    for _ in 0..3 {
+        let (_, start, len) = der_header(&pkcs7, pos)?;
+        pos = start + len;
+    }
+    let (tag, certs_start, _) = der_header(&pkcs7, pos)?;
+    if tag != 0xa0 {
+        return None;
+    }
+
+    let (_, cert_content, cert_len) = der_header(&pkcs7, certs_start)?;
+    pkcs7
+        .get(certs_start..cert_content + cert_len)
+        .map(|c| c.to_vec())
+}
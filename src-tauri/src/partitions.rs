@@ -0,0 +1,421 @@
+//! Raw partition images for rooted devices.
+//!
+//! Partitions are resolved through `/dev/block/by-name` (active slot first on
+//! A/B devices) and streamed to the PC with `adb exec-out su -c dd`, hashing
+//! while writing. Every `<block>.img` gets a `<block>.img.json` next to it
+//! with the SHA-256, size and the device fingerprint. Images are written back
+//! with `fastboot flash` after the hash is checked again.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::adb::{self, run_command, run_fastboot_command};
+use crate::backup_naming;
+use crate::package_info;
+
+/// Partitions worth keeping before flashing anything
+pub const CRITICAL_PARTITIONS: &[&str] = &[
+    "boot",
+    "init_boot",
+    "vendor_boot",
+    "dtbo",
+    "vbmeta",
+    "vbmeta_system",
+    "persist",
+    "modem",
+    "modemst1",
+    "modemst2",
+    "fsg",
+    "efs",
+];
+
+const BY_NAME_DIRS: &[&str] = &["/dev/block/by-name", "/dev/block/bootdevice/by-name"];
+const READ_BUFFER: usize = 1024 * 1024;
+/// Emit progress every 8 MB
+const PROGRESS_STEP: u64 = 8 * 1024 * 1024;
+
+/// Cihazdaki bölüm
+#[derive(Serialize, Debug, Clone)]
+pub struct PartitionInfo {
+    /// Name without slot suffix (e.g. "boot")
+    pub name: String,
+    /// Entry under by-name (e.g. "boot_a")
+    pub block_name: String,
+    pub path: String,
+    pub size: Option<u64>,
+    pub slot: Option<String>,
+}
+
+/// Imaj yanındaki `.img.json`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PartitionImage {
+    pub name: String,
+    pub block_name: String,
+    pub file: String,
+    pub size: u64,
+    pub sha256: String,
+    /// Hash computed on the device matched the local one
+    pub device_verified: bool,
+    /// adb serial the image was taken from
+    pub serial: String,
+    /// `ro.serialno`, what fastboot reports as `serialno`
+    #[serde(default)]
+    pub serialno: Option<String>,
+    /// `ro.product.device`, what fastboot reports as `product`
+    #[serde(default)]
+    pub product: Option<String>,
+    pub fingerprint: Option<String>,
+    pub slot: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PartitionBackupReport {
+    pub folder: String,
+    pub images: Vec<PartitionImage>,
+    pub failed: Vec<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct PartitionProgress {
+    pub partition: String,
+    pub bytes: u64,
+    pub total: Option<u64>,
+}
+
+fn su(device_id: &str, cmd: &str) -> Result<String, String> {
+    run_command(&["-s", device_id, "shell", "su", "-c", cmd])
+}
+
+/// Active slot suffix ("_a" / "_b"), `None` on non-A/B devices.
+pub fn slot_suffix(device_id: &str) -> Option<String> {
+    package_info::get_prop(device_id, "ro.boot.slot_suffix")
+}
+
+/// Lists the by-name entries as (by-name dir, entry names).
+fn by_name_entries(device_id: &str) -> Result<(String, Vec<String>), String> {
+    for dir in BY_NAME_DIRS {
+        if let Ok(out) = su(device_id, &format!("ls {}", dir)) {
+            let names: Vec<String> = out
+                .split_whitespace()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+            if !names.is_empty() {
+                return Ok((dir.to_string(), names));
+            }
+        }
+    }
+    Err("Could not list /dev/block/by-name (root required)".to_string())
+}
+
+fn block_size(device_id: &str, path: &str) -> Option<u64> {
+    su(device_id, &format!("blockdev --getsize64 {}", path))
+        .ok()
+        .and_then(|out| out.trim().parse().ok())
+}
+
+/// Resolves partition names to block devices, preferring the active slot.
+pub fn resolve(device_id: &str, names: &[String]) -> Result<Vec<PartitionInfo>, String> {
+    let (dir, entries) = by_name_entries(device_id)?;
+    let slot = slot_suffix(device_id);
+
+    let mut resolved = Vec::new();
+    for name in names {
+        let slotted = slot.as_ref().map(|s| format!("{}{}", name, s));
+        let block_name = match slotted {
+            Some(slotted) if entries.contains(&slotted) => slotted,
+            _ if entries.contains(name) => name.clone(),
+            _ => continue,
+        };
+        let path = format!("{}/{}", dir, block_name);
+        resolved.push(PartitionInfo {
+            name: name.clone(),
+            slot: slot.clone().filter(|s| block_name.ends_with(s.as_str())),
+            size: block_size(device_id, &path),
+            block_name,
+            path,
+        });
+    }
+    Ok(resolved)
+}
+
+/// Critical partitions present on the device.
+pub fn list_critical(device_id: &str) -> Result<Vec<PartitionInfo>, String> {
+    let names: Vec<String> = CRITICAL_PARTITIONS.iter().map(|s| s.to_string()).collect();
+    resolve(device_id, &names)
+}
+
+/// Copies the `dd` output into `dest`, hashing and reporting progress.
+/// Returns (bytes, sha256).
+fn copy_stream<R, F>(
+    mut stdout: R,
+    partition: &PartitionInfo,
+    dest: &Path,
+    on_progress: &F,
+) -> Result<(u64, String), String>
+where
+    R: Read,
+    F: Fn(PartitionProgress),
+{
+    let mut out = File::create(dest).map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; READ_BUFFER];
+    let mut bytes: u64 = 0;
+    let mut next_report = 0;
+
+    loop {
+        let n = stdout
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read {}: {}", partition.block_name, e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+        out.write_all(&buffer[..n]).map_err(|e| e.to_string())?;
+        bytes += n as u64;
+
+        if bytes >= next_report {
+            on_progress(PartitionProgress {
+                partition: partition.block_name.clone(),
+                bytes,
+                total: partition.size,
+            });
+            next_report = bytes + PROGRESS_STEP;
+        }
+    }
+
+    Ok((bytes, format!("{:x}", hasher.finalize())))
+}
+
+/// Streams one partition into `dest`. Returns (bytes, sha256).
+fn dump_partition<F>(
+    device_id: &str,
+    partition: &PartitionInfo,
+    dest: &Path,
+    on_progress: &F,
+) -> Result<(u64, String), String>
+where
+    F: Fn(PartitionProgress),
+{
+    let dd = format!("dd if={} bs={} 2>/dev/null", partition.path, READ_BUFFER);
+    let mut child = adb::spawn_command(&["-s", device_id, "exec-out", "su", "-c", &dd])?;
+
+    let copied = match child.stdout.take() {
+        Some(stdout) => copy_stream(stdout, partition, dest, on_progress),
+        None => Err("Failed to capture stdout".to_string()),
+    };
+    let (bytes, hash) = match copied {
+        Ok(result) => result,
+        Err(e) => {
+            // Don't leave dd running on the device or an adb zombie behind
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(e);
+        }
+    };
+
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Err(format!("dd failed for {}", partition.block_name));
+    }
+    if bytes == 0 {
+        return Err(format!("No data read from {}", partition.block_name));
+    }
+    if let Some(size) = partition.size {
+        if bytes != size {
+            return Err(format!(
+                "Short read for {}: {} of {} bytes",
+                partition.block_name, bytes, size
+            ));
+        }
+    }
+
+    Ok((bytes, hash))
+}
+
+/// Dumps `names` into a new `<model>_<datetime>` folder under `dest_root`.
+pub fn backup<F>(
+    device_id: &str,
+    names: &[String],
+    dest_root: &Path,
+    on_progress: F,
+) -> Result<PartitionBackupReport, String>
+where
+    F: Fn(PartitionProgress),
+{
+    if !adb::is_device_rooted(device_id) {
+        return Err("Partition backup requires root".to_string());
+    }
+
+    let partitions = resolve(device_id, names)?;
+    let fingerprint = package_info::get_prop(device_id, "ro.build.fingerprint");
+    let serialno = package_info::get_prop(device_id, "ro.serialno");
+    let product = package_info::get_prop(device_id, "ro.product.device");
+    let model = package_info::get_prop(device_id, "ro.product.model")
+        .unwrap_or_else(|| device_id.to_string());
+    let now = chrono::Local::now();
+    let folder = dest_root.join(format!(
+        "{}_partitions_{}",
+        backup_naming::sanitize_component(&model),
+        now.format("%Y%m%d_%H%M%S")
+    ));
+    fs::create_dir_all(&folder).map_err(|e| e.to_string())?;
+
+    let mut report = PartitionBackupReport {
+        folder: folder.to_string_lossy().to_string(),
+        images: Vec::new(),
+        failed: names
+            .iter()
+            .filter(|n| !partitions.iter().any(|p| &p.name == *n))
+            .map(|n| format!("{}: not found", n))
+            .collect(),
+    };
+
+    for partition in &partitions {
+        let file_name = format!("{}.img", partition.block_name);
+        let image_path = folder.join(&file_name);
+
+        let (size, sha256) = match dump_partition(device_id, partition, &image_path, &on_progress) {
+            Ok(result) => result,
+            Err(e) => {
+                let _ = fs::remove_file(&image_path);
+                report
+                    .failed
+                    .push(format!("{}: {}", partition.block_name, e));
+                continue;
+            }
+        };
+
+        // Cross-check with the device's own hash when toybox has sha256sum
+        let device_hash = su(device_id, &format!("sha256sum {}", partition.path))
+            .ok()
+            .and_then(|out| out.split_whitespace().next().map(|h| h.to_lowercase()));
+        if device_hash.as_ref().is_some_and(|h| *h != sha256) {
+            let _ = fs::remove_file(&image_path);
+            report
+                .failed
+                .push(format!("{}: checksum mismatch", partition.block_name));
+            continue;
+        }
+
+        let image = PartitionImage {
+            name: partition.name.clone(),
+            block_name: partition.block_name.clone(),
+            file: file_name,
+            size,
+            sha256,
+            device_verified: device_hash.is_some(),
+            serial: device_id.to_string(),
+            serialno: serialno.clone(),
+            product: product.clone(),
+            fingerprint: fingerprint.clone(),
+            slot: partition.slot.clone(),
+            created_at: now.to_rfc3339(),
+        };
+        let json = serde_json::to_string_pretty(&image).map_err(|e| e.to_string())?;
+        fs::write(sidecar_path(&image_path), json).map_err(|e| e.to_string())?;
+        report.images.push(image);
+    }
+
+    Ok(report)
+}
+
+fn sidecar_path(image_path: &Path) -> PathBuf {
+    let mut name = image_path.as_os_str().to_owned();
+    name.push(".json");
+    PathBuf::from(name)
+}
+
+fn file_sha256(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open image: {}", e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; READ_BUFFER];
+    loop {
+        let n = file.read(&mut buffer).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Device codename from a build fingerprint ("brand/product/device:ver/...").
+fn fingerprint_device(fingerprint: &str) -> Option<&str> {
+    fingerprint.split('/').nth(2)?.split(':').next()
+}
+
+/// Refuses to flash an image taken from another device.
+fn check_same_device(sidecar: &PartitionImage, serial: Option<&str>) -> Result<(), String> {
+    let serialno = adb::fastboot_getvar(serial, "serialno")?;
+    let expected_serial = sidecar.serialno.as_deref().unwrap_or(&sidecar.serial);
+    if serialno != expected_serial {
+        return Err(format!(
+            "Image was taken from device {}, but the connected device is {}; refusing to flash",
+            expected_serial, serialno
+        ));
+    }
+
+    let product = adb::fastboot_getvar(serial, "product")?;
+    let expected_product = sidecar
+        .product
+        .as_deref()
+        .or_else(|| sidecar.fingerprint.as_deref().and_then(fingerprint_device))
+        .ok_or("Image sidecar has no device model; can't verify it")?;
+    if !product.eq_ignore_ascii_case(expected_product) {
+        return Err(format!(
+            "Image was taken from a {}, but the connected device is a {}; refusing to flash",
+            expected_product, product
+        ));
+    }
+    Ok(())
+}
+
+/// The partition the image came from, with or without a slot suffix.
+fn is_same_partition(target: &str, sidecar: &PartitionImage) -> bool {
+    target == sidecar.block_name
+        || target
+            .strip_prefix(sidecar.name.as_str())
+            .is_some_and(|suffix| matches!(suffix, "" | "_a" | "_b"))
+}
+
+/// Flashes an image with fastboot (device must be in bootloader mode).
+/// The partition defaults to the block name recorded in the sidecar; an
+/// override may only change the slot. The image must still match its
+/// recorded SHA-256, and the device its recorded serial and model.
+pub fn flash_image(
+    image_path: &Path,
+    serial: Option<&str>,
+    partition: Option<&str>,
+) -> Result<String, String> {
+    let sidecar: PartitionImage = fs::read_to_string(sidecar_path(image_path))
+        .map_err(|_| "Image has no .json sidecar; can't verify it".to_string())
+        .and_then(|c| serde_json::from_str(&c).map_err(|e| e.to_string()))?;
+
+    if file_sha256(image_path)? != sidecar.sha256 {
+        return Err("Image checksum does not match its sidecar; refusing to flash".to_string());
+    }
+
+    let target = partition.unwrap_or(&sidecar.block_name);
+    if !is_same_partition(target, &sidecar) {
+        return Err(format!(
+            "Image of {} can't be flashed to {}",
+            sidecar.name, target
+        ));
+    }
+    check_same_device(&sidecar, serial)?;
+
+    let image = image_path.to_string_lossy().to_string();
+    let mut args = Vec::new();
+    if let Some(serial) = serial {
+        args.extend(["-s", serial]);
+    }
+    args.extend(["flash", target, image.as_str()]);
+
+    run_fastboot_command(&args)?;
+    Ok(format!("Flashed {} to {}", sidecar.file, target))
+}
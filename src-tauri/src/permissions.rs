@@ -0,0 +1,221 @@
+//! Runtime permission and app-ops state.
+//!
+//! Captured from `dumpsys package` / `appops get` during backup and re-applied
+//! with `pm grant` / `appops set` on restore, so restored apps don't ask for
+//! camera, location or notification access again.
+
+use serde::{Deserialize, Serialize};
+
+use crate::adb::run_command;
+use crate::package_info;
+
+/// Yedekteki izin durumu (permissions.json)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionState {
+    pub granted_permissions: Vec<String>,
+    pub app_ops: Vec<AppOpEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AppOpEntry {
+    pub op: String,
+    /// allow, ignore, deny, default, foreground
+    pub mode: String,
+    /// Listed under "Uid mode:" and set with `appops set --uid`
+    pub uid_mode: bool,
+}
+
+impl PermissionState {
+    pub fn is_empty(&self) -> bool {
+        self.granted_permissions.is_empty() && self.app_ops.is_empty()
+    }
+}
+
+/// Parses granted runtime permissions from `dumpsys package <pkg>`.
+/// Only the first user's block is used (user 0 unless `user` is given).
+pub fn parse_runtime_permissions(output: &str, package_name: &str, user: u32) -> Vec<String> {
+    let section = package_info::package_section(output, package_name);
+    let user_header = format!("User {}:", user);
+
+    let mut in_user = false;
+    let mut in_runtime = false;
+    let mut runtime_indent = 0;
+    let mut granted = Vec::new();
+
+    for line in section {
+        let trimmed = line.trim();
+        let indent = line.len() - line.trim_start().len();
+
+        if trimmed.starts_with("User ") {
+            in_user = trimmed.starts_with(&user_header);
+            in_runtime = false;
+            continue;
+        }
+        if !in_user {
+            continue;
+        }
+
+        if trimmed == "runtime permissions:" {
+            in_runtime = true;
+            runtime_indent = indent;
+            continue;
+        }
+        if in_runtime {
+            if indent <= runtime_indent {
+                in_runtime = false;
+                continue;
+            }
+            // android.permission.CAMERA: granted=true, flags=[ USER_SET ]
+            if let Some((name, rest)) = trimmed.split_once(':') {
+                if rest.contains("granted=true") {
+                    granted.push(name.trim().to_string());
+                }
+            }
+        }
+    }
+
+    granted
+}
+
+/// Parses `appops get <pkg>` output.
+///
+/// ```text
+/// Uid mode: COARSE_LOCATION: foreground
+/// CAMERA: allow; time=+1d2h ago
+/// RUN_IN_BACKGROUND: ignore
+/// ```
+pub fn parse_app_ops(output: &str) -> Vec<AppOpEntry> {
+    let mut ops = Vec::new();
+
+    for line in output.lines() {
+        let mut line = line.trim();
+        let uid_mode = line.starts_with("Uid mode:");
+        if uid_mode {
+            line = line.trim_start_matches("Uid mode:").trim();
+        }
+
+        let Some((op, rest)) = line.split_once(':') else {
+            continue;
+        };
+        let op = op.trim();
+        let mode = rest.split(';').next().unwrap_or("").trim();
+
+        // Op names are upper-case identifiers; skip headers and free text
+        if !is_valid_op(op) || mode.is_empty() || mode.contains(' ') {
+            continue;
+        }
+
+        ops.push(AppOpEntry {
+            op: op.to_string(),
+            mode: mode.to_string(),
+            uid_mode,
+        });
+    }
+
+    ops
+}
+
+/// App-op modes `appops set` accepts
+const APP_OP_MODES: [&str; 5] = ["allow", "ignore", "deny", "default", "foreground"];
+
+/// `android.permission.CAMERA`, `com.foo.permission.C2D_MESSAGE`
+fn is_valid_permission(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// `CAMERA`, `RUN_IN_BACKGROUND`
+fn is_valid_op(op: &str) -> bool {
+    !op.is_empty()
+        && op
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Reads the current permission and app-ops state of an installed app.
+pub fn capture(device_id: &str, package_name: &str) -> PermissionState {
+    let granted_permissions =
+        run_command(&["-s", device_id, "shell", "dumpsys", "package", package_name])
+            .map(|out| parse_runtime_permissions(&out, package_name, 0))
+            .unwrap_or_default();
+
+    let app_ops = run_command(&["-s", device_id, "shell", "appops", "get", package_name])
+        .map(|out| parse_app_ops(&out))
+        .unwrap_or_default();
+
+    PermissionState {
+        granted_permissions,
+        app_ops,
+    }
+}
+
+/// Re-applies permissions and app-ops. Returns the entries that couldn't be
+/// applied (unknown on this Android version, not requested by the app...).
+/// permissions.json comes from the archive and ends up in a shell command, so
+/// entries with unexpected characters are reported as failures and never run.
+pub fn apply(
+    device_id: &str,
+    package_name: &str,
+    state: &PermissionState,
+    user: Option<u32>,
+) -> Vec<String> {
+    let mut failures = Vec::new();
+    let user_str = user.map(|u| u.to_string());
+
+    for perm in &state.granted_permissions {
+        if !is_valid_permission(perm) {
+            failures.push(format!("{}: invalid permission name", perm));
+            continue;
+        }
+
+        let mut args = vec!["-s", device_id, "shell", "pm", "grant"];
+        if let Some(u) = &user_str {
+            args.extend(["--user", u.as_str()]);
+        }
+        args.extend([package_name, perm.as_str()]);
+
+        if let Err(e) = run_command(&args) {
+            failures.push(format!("{}: {}", perm, first_line(&e)));
+        }
+    }
+
+    for entry in &state.app_ops {
+        if !is_valid_op(&entry.op) {
+            failures.push(format!(
+                "{} ({}): invalid app-op name",
+                entry.op, entry.mode
+            ));
+            continue;
+        }
+        if !APP_OP_MODES.contains(&entry.mode.as_str()) {
+            failures.push(format!(
+                "{} ({}): invalid app-op mode",
+                entry.op, entry.mode
+            ));
+            continue;
+        }
+
+        let mut args = vec!["-s", device_id, "shell", "appops", "set"];
+        if let Some(u) = &user_str {
+            args.extend(["--user", u.as_str()]);
+        }
+        if entry.uid_mode {
+            args.push("--uid");
+        }
+        args.extend([package_name, entry.op.as_str(), entry.mode.as_str()]);
+
+        if let Err(e) = run_command(&args) {
+            failures.push(format!("{} ({}): {}", entry.op, entry.mode, first_line(&e)));
+        }
+    }
+
+    failures
+}
+
+fn first_line(message: &str) -> &str {
+    message.lines().next().unwrap_or(message).trim()
+}
@@ -0,0 +1,553 @@
+//! Contacts, SMS and call log export through Android content providers.
+//!
+//! Each dataset is read with `content query --uri ... --projection ...` and
+//! the `Row: N col=value, ...` output is parsed back into structs. Contacts
+//! are written as vCard 3.0, SMS and calls as XML (the "SMS Backup & Restore"
+//! layout most importers accept) and JSON. Everything ends up in one zip next
+//! to a small `personal_data.json` summary.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use zip::write::FileOptions;
+
+use crate::adb;
+use crate::backup_naming;
+use crate::package_info;
+
+const CONTACTS_URI: &str = "content://com.android.contacts/data";
+const SMS_URI: &str = "content://sms";
+const CALL_LOG_URI: &str = "content://call_log/calls";
+
+// The free-text column goes last: a value can only be cut short by a
+// following `, column=`, and the last one has none.
+const CONTACT_COLUMNS: &[&str] = &[
+    "contact_id",
+    "mimetype",
+    "display_name",
+    "data2",
+    "data3",
+    "data4",
+    "data1",
+];
+const SMS_COLUMNS: &[&str] = &[
+    "_id",
+    "thread_id",
+    "address",
+    "date",
+    "date_sent",
+    "read",
+    "type",
+    "body",
+];
+const CALL_COLUMNS: &[&str] = &["_id", "date", "duration", "type", "number", "name"];
+
+const MIME_NAME: &str = "vnd.android.cursor.item/name";
+const MIME_PHONE: &str = "vnd.android.cursor.item/phone_v2";
+const MIME_EMAIL: &str = "vnd.android.cursor.item/email_v2";
+const MIME_ORGANIZATION: &str = "vnd.android.cursor.item/organization";
+const MIME_NOTE: &str = "vnd.android.cursor.item/note";
+
+type Row = BTreeMap<String, Option<String>>;
+
+/// Dışa aktarılacak veri setleri
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct PersonalDataSelection {
+    pub contacts: bool,
+    pub sms: bool,
+    pub call_log: bool,
+}
+
+impl Default for PersonalDataSelection {
+    fn default() -> Self {
+        PersonalDataSelection {
+            contacts: true,
+            sms: true,
+            call_log: true,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct PersonalDataReport {
+    pub path: String,
+    /// `None` when the dataset wasn't selected or couldn't be read
+    pub contacts: Option<usize>,
+    pub sms: Option<usize>,
+    pub call_log: Option<usize>,
+    pub failed: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+struct ExportSummary {
+    serial: String,
+    model: Option<String>,
+    created_at: String,
+    contacts: Option<usize>,
+    sms: Option<usize>,
+    call_log: Option<usize>,
+}
+
+/// Rehber kişisi
+#[derive(Debug, Clone, Default)]
+struct Contact {
+    display_name: Option<String>,
+    given_name: Option<String>,
+    family_name: Option<String>,
+    /// (vCard TYPE, number)
+    phones: Vec<(&'static str, String)>,
+    emails: Vec<(&'static str, String)>,
+    organization: Option<String>,
+    title: Option<String>,
+    note: Option<String>,
+}
+
+/// SMS kaydı
+#[derive(Serialize, Debug, Clone)]
+pub struct SmsMessage {
+    pub id: i64,
+    pub thread_id: Option<i64>,
+    pub address: Option<String>,
+    /// Epoch millis
+    pub date: i64,
+    pub date_sent: Option<i64>,
+    pub read: bool,
+    /// 1 = inbox, 2 = sent, 3 = draft, 4 = outbox, 5 = failed, 6 = queued
+    #[serde(rename = "type")]
+    pub kind: i32,
+    pub body: String,
+}
+
+/// Arama kaydı
+#[derive(Serialize, Debug, Clone)]
+pub struct CallLogEntry {
+    pub id: i64,
+    pub number: Option<String>,
+    pub name: Option<String>,
+    /// Epoch millis
+    pub date: i64,
+    /// Seconds
+    pub duration: i64,
+    /// 1 = incoming, 2 = outgoing, 3 = missed, 4 = voicemail, 5 = rejected, 6 = blocked
+    #[serde(rename = "type")]
+    pub kind: i32,
+}
+
+/// Runs `content query`. `run_command` isn't used because it treats any
+/// "error:" in stdout as a failure, and message bodies can contain that.
+fn content_query(device_id: &str, uri: &str, columns: &[&str]) -> Result<String, String> {
+    let projection = columns.join(":");
+    let child = adb::spawn_command(&[
+        "-s",
+        device_id,
+        "exec-out",
+        "content",
+        "query",
+        "--uri",
+        uri,
+        "--projection",
+        &projection,
+    ])?;
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Failed to query {}: {}", uri, stderr.trim()));
+    }
+    // Provider errors are printed instead of rows
+    if !stdout.starts_with("Row: ") && !stdout.starts_with("No result found") {
+        if let Some(line) = stdout.lines().find(|l| !l.trim().is_empty()) {
+            return Err(format!("Failed to query {}: {}", uri, line.trim()));
+        }
+    }
+    Ok(stdout)
+}
+
+/// Parses `content query` output. `columns` must be the projection used for
+/// the query, in order. Values with newlines continue on the next lines.
+fn parse_rows(output: &str, columns: &[&str]) -> Vec<Row> {
+    let mut records: Vec<String> = Vec::new();
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("Row: ") {
+            // Drop the row index
+            let fields = rest.split_once(' ').map(|(_, f)| f).unwrap_or("");
+            records.push(fields.to_string());
+        } else if let Some(last) = records.last_mut() {
+            last.push('\n');
+            last.push_str(line);
+        }
+    }
+
+    records
+        .iter()
+        .filter_map(|record| parse_record(record, columns))
+        .collect()
+}
+
+fn parse_record(record: &str, columns: &[&str]) -> Option<Row> {
+    let mut row = Row::new();
+    let mut rest = record;
+    for (i, column) in columns.iter().enumerate() {
+        rest = rest.strip_prefix(&format!("{}=", column))?;
+        let value = match columns.get(i + 1) {
+            Some(next) => {
+                let separator = format!(", {}=", next);
+                let end = rest.find(&separator)?;
+                let value = &rest[..end];
+                rest = &rest[end + 2..];
+                value
+            }
+            None => rest,
+        };
+        let value = (value != "NULL").then(|| value.to_string());
+        row.insert(column.to_string(), value);
+    }
+    Some(row)
+}
+
+fn text(row: &Row, column: &str) -> Option<String> {
+    row.get(column)
+        .cloned()
+        .flatten()
+        .filter(|v| !v.trim().is_empty())
+}
+
+fn number(row: &Row, column: &str) -> Option<i64> {
+    text(row, column).and_then(|v| v.trim().parse().ok())
+}
+
+fn phone_type(code: Option<i64>) -> &'static str {
+    match code {
+        Some(1) => "HOME",
+        Some(2) => "CELL",
+        Some(3) => "WORK",
+        Some(4) => "WORK,FAX",
+        Some(5) => "HOME,FAX",
+        Some(6) => "PAGER",
+        _ => "VOICE",
+    }
+}
+
+fn email_type(code: Option<i64>) -> &'static str {
+    match code {
+        Some(1) => "INTERNET,HOME",
+        Some(2) => "INTERNET,WORK",
+        _ => "INTERNET",
+    }
+}
+
+fn read_contacts(device_id: &str) -> Result<Vec<Contact>, String> {
+    let output = content_query(device_id, CONTACTS_URI, CONTACT_COLUMNS)?;
+    let mut contacts: BTreeMap<i64, Contact> = BTreeMap::new();
+
+    for row in parse_rows(&output, CONTACT_COLUMNS) {
+        let Some(id) = number(&row, "contact_id") else {
+            continue;
+        };
+        let contact = contacts.entry(id).or_default();
+        if contact.display_name.is_none() {
+            contact.display_name = text(&row, "display_name");
+        }
+
+        let data1 = text(&row, "data1");
+        match text(&row, "mimetype").as_deref() {
+            Some(MIME_NAME) => {
+                contact.given_name = text(&row, "data2");
+                contact.family_name = text(&row, "data3");
+            }
+            Some(MIME_PHONE) => {
+                if let Some(value) = data1 {
+                    contact
+                        .phones
+                        .push((phone_type(number(&row, "data2")), value));
+                }
+            }
+            Some(MIME_EMAIL) => {
+                if let Some(value) = data1 {
+                    contact
+                        .emails
+                        .push((email_type(number(&row, "data2")), value));
+                }
+            }
+            Some(MIME_ORGANIZATION) => {
+                contact.organization = data1;
+                contact.title = text(&row, "data4");
+            }
+            Some(MIME_NOTE) => contact.note = data1,
+            _ => {}
+        }
+    }
+
+    Ok(contacts
+        .into_values()
+        .filter(|c| c.display_name.is_some() || !c.phones.is_empty() || !c.emails.is_empty())
+        .collect())
+}
+
+fn read_sms(device_id: &str) -> Result<Vec<SmsMessage>, String> {
+    let output = content_query(device_id, SMS_URI, SMS_COLUMNS)?;
+    let mut messages: Vec<SmsMessage> = parse_rows(&output, SMS_COLUMNS)
+        .iter()
+        .map(|row| SmsMessage {
+            id: number(row, "_id").unwrap_or_default(),
+            thread_id: number(row, "thread_id"),
+            address: text(row, "address"),
+            date: number(row, "date").unwrap_or_default(),
+            date_sent: number(row, "date_sent").filter(|d| *d > 0),
+            read: number(row, "read") == Some(1),
+            kind: number(row, "type").unwrap_or(1) as i32,
+            body: row.get("body").cloned().flatten().unwrap_or_default(),
+        })
+        .collect();
+    messages.sort_by_key(|m| m.date);
+    Ok(messages)
+}
+
+fn read_call_log(device_id: &str) -> Result<Vec<CallLogEntry>, String> {
+    let output = content_query(device_id, CALL_LOG_URI, CALL_COLUMNS)?;
+    let mut calls: Vec<CallLogEntry> = parse_rows(&output, CALL_COLUMNS)
+        .iter()
+        .map(|row| CallLogEntry {
+            id: number(row, "_id").unwrap_or_default(),
+            number: text(row, "number"),
+            name: text(row, "name"),
+            date: number(row, "date").unwrap_or_default(),
+            duration: number(row, "duration").unwrap_or_default(),
+            kind: number(row, "type").unwrap_or(1) as i32,
+        })
+        .collect();
+    calls.sort_by_key(|c| c.date);
+    Ok(calls)
+}
+
+fn vcard_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Folds a content line at 75 octets (RFC 2425), never inside a UTF-8 character.
+fn vcard_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn to_vcard(contacts: &[Contact]) -> String {
+    let mut out = String::new();
+    for contact in contacts {
+        let full_name = contact
+            .display_name
+            .clone()
+            .or_else(|| contact.phones.first().map(|(_, p)| p.clone()))
+            .or_else(|| contact.emails.first().map(|(_, e)| e.clone()))
+            .unwrap_or_default();
+
+        vcard_line(&mut out, "BEGIN:VCARD");
+        vcard_line(&mut out, "VERSION:3.0");
+        vcard_line(&mut out, &format!("FN:{}", vcard_escape(&full_name)));
+        vcard_line(
+            &mut out,
+            &format!(
+                "N:{};{};;;",
+                vcard_escape(contact.family_name.as_deref().unwrap_or("")),
+                vcard_escape(contact.given_name.as_deref().unwrap_or(""))
+            ),
+        );
+        for (kind, phone) in &contact.phones {
+            vcard_line(
+                &mut out,
+                &format!("TEL;TYPE={}:{}", kind, vcard_escape(phone)),
+            );
+        }
+        for (kind, email) in &contact.emails {
+            vcard_line(
+                &mut out,
+                &format!("EMAIL;TYPE={}:{}", kind, vcard_escape(email)),
+            );
+        }
+        if let Some(org) = &contact.organization {
+            vcard_line(&mut out, &format!("ORG:{}", vcard_escape(org)));
+        }
+        if let Some(title) = &contact.title {
+            vcard_line(&mut out, &format!("TITLE:{}", vcard_escape(title)));
+        }
+        if let Some(note) = &contact.note {
+            vcard_line(&mut out, &format!("NOTE:{}", vcard_escape(note)));
+        }
+        vcard_line(&mut out, "END:VCARD");
+    }
+    out
+}
+
+fn xml_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\n' => out.push_str("&#10;"),
+            '\r' => out.push_str("&#13;"),
+            '\t' => out.push_str("&#9;"),
+            // Other control characters aren't allowed in XML 1.0
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn readable_date(millis: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(millis)
+        .map(|d| {
+            d.with_timezone(&chrono::Local)
+                .format("%d %b %Y %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+fn sms_to_xml(messages: &[SmsMessage]) -> String {
+    let mut out = String::from("<?xml version='1.0' encoding='UTF-8' standalone='yes' ?>\n");
+    out.push_str(&format!("<smses count=\"{}\">\n", messages.len()));
+    for m in messages {
+        out.push_str(&format!(
+            "  <sms protocol=\"0\" address=\"{}\" date=\"{}\" type=\"{}\" subject=\"null\" body=\"{}\" toa=\"null\" sc_toa=\"null\" service_center=\"null\" read=\"{}\" status=\"-1\" locked=\"0\" date_sent=\"{}\" readable_date=\"{}\" contact_name=\"(Unknown)\" />\n",
+            xml_escape(m.address.as_deref().unwrap_or("")),
+            m.date,
+            m.kind,
+            xml_escape(&m.body),
+            u8::from(m.read),
+            m.date_sent.unwrap_or(0),
+            readable_date(m.date),
+        ));
+    }
+    out.push_str("</smses>\n");
+    out
+}
+
+fn calls_to_xml(calls: &[CallLogEntry]) -> String {
+    let mut out = String::from("<?xml version='1.0' encoding='UTF-8' standalone='yes' ?>\n");
+    out.push_str(&format!("<calls count=\"{}\">\n", calls.len()));
+    for c in calls {
+        out.push_str(&format!(
+            "  <call number=\"{}\" duration=\"{}\" date=\"{}\" type=\"{}\" presentation=\"1\" readable_date=\"{}\" contact_name=\"{}\" />\n",
+            xml_escape(c.number.as_deref().unwrap_or("")),
+            c.duration,
+            c.date,
+            c.kind,
+            readable_date(c.date),
+            xml_escape(c.name.as_deref().unwrap_or("(Unknown)")),
+        ));
+    }
+    out.push_str("</calls>\n");
+    out
+}
+
+/// Exports the selected datasets into `<model>_personal_<datetime>.zip` under
+/// `dest_root`. A dataset that can't be read is listed in `failed`; the export
+/// only fails when nothing could be read.
+pub fn export(
+    device_id: &str,
+    selection: &PersonalDataSelection,
+    dest_root: &Path,
+) -> Result<PersonalDataReport, String> {
+    if !(selection.contacts || selection.sms || selection.call_log) {
+        return Err("No dataset selected".to_string());
+    }
+
+    let mut report = PersonalDataReport::default();
+    // (file name, contents)
+    let mut files: Vec<(&str, String)> = Vec::new();
+
+    if selection.contacts {
+        match read_contacts(device_id) {
+            Ok(contacts) => {
+                report.contacts = Some(contacts.len());
+                files.push(("contacts.vcf", to_vcard(&contacts)));
+            }
+            Err(e) => report.failed.push(format!("contacts: {}", e)),
+        }
+    }
+    if selection.sms {
+        match read_sms(device_id) {
+            Ok(messages) => {
+                report.sms = Some(messages.len());
+                files.push(("sms.xml", sms_to_xml(&messages)));
+                let json = serde_json::to_string_pretty(&messages).map_err(|e| e.to_string())?;
+                files.push(("sms.json", json));
+            }
+            Err(e) => report.failed.push(format!("sms: {}", e)),
+        }
+    }
+    if selection.call_log {
+        match read_call_log(device_id) {
+            Ok(calls) => {
+                report.call_log = Some(calls.len());
+                files.push(("call_log.xml", calls_to_xml(&calls)));
+                let json = serde_json::to_string_pretty(&calls).map_err(|e| e.to_string())?;
+                files.push(("call_log.json", json));
+            }
+            Err(e) => report.failed.push(format!("call_log: {}", e)),
+        }
+    }
+
+    if files.is_empty() {
+        return Err(format!(
+            "Nothing could be exported: {}",
+            report.failed.join("; ")
+        ));
+    }
+
+    let model = package_info::get_prop(device_id, "ro.product.model");
+    let now = chrono::Local::now();
+    let summary = ExportSummary {
+        serial: device_id.to_string(),
+        model: model.clone(),
+        created_at: now.to_rfc3339(),
+        contacts: report.contacts,
+        sms: report.sms,
+        call_log: report.call_log,
+    };
+    files.push((
+        "personal_data.json",
+        serde_json::to_string_pretty(&summary).map_err(|e| e.to_string())?,
+    ));
+
+    fs::create_dir_all(dest_root).map_err(|e| e.to_string())?;
+    let path = backup_naming::unique_path(&dest_root.join(format!(
+        "{}_personal_{}.zip",
+        backup_naming::sanitize_component(model.as_deref().unwrap_or(device_id)),
+        now.format("%Y%m%d_%H%M%S")
+    )));
+
+    let file = File::create(&path).map_err(|e| format!("Failed to create archive: {}", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = FileOptions::<()>::default().compression_method(zip::CompressionMethod::Deflated);
+    for (name, contents) in &files {
+        zip.start_file(*name, options).map_err(|e| e.to_string())?;
+        zip.write_all(contents.as_bytes())
+            .map_err(|e| e.to_string())?;
+    }
+    zip.finish().map_err(|e| e.to_string())?;
+
+    report.path = path.to_string_lossy().to_string();
+    Ok(report)
+}
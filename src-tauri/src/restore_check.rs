@@ -0,0 +1,260 @@
+//! Restore pre-flight: compares a backup against the target device before
+//! anything is installed, so the UI can show problems up front instead of
+//! waiting for `adb install` to fail.
+
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use crate::adb::{self, run_command, RestoreOptions};
+use crate::backup_manifest;
+use crate::package_info::{self, PackageDetails};
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PreflightIssue {
+    /// Machine readable code (e.g. `SDK_TOO_LOW`) for the frontend
+    pub code: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RestorePreflight {
+    pub package_name: Option<String>,
+    /// False when at least one blocking error was found
+    pub can_restore: bool,
+    pub errors: Vec<PreflightIssue>,
+    pub warnings: Vec<PreflightIssue>,
+}
+
+impl RestorePreflight {
+    fn error(&mut self, code: &str, message: String) {
+        self.errors.push(PreflightIssue {
+            code: code.to_string(),
+            message,
+        });
+    }
+
+    fn warning(&mut self, code: &str, message: String) {
+        self.warnings.push(PreflightIssue {
+            code: code.to_string(),
+            message,
+        });
+    }
+}
+
+/// Device ABI list in preference order (`ro.product.cpu.abilist`).
+pub fn get_device_abis(device_id: &str) -> Vec<String> {
+    package_info::get_prop(device_id, "ro.product.cpu.abilist")
+        .or_else(|| package_info::get_prop(device_id, "ro.product.cpu.abi"))
+        .map(|list| {
+            list.split(',')
+                .map(|a| a.trim().to_string())
+                .filter(|a| !a.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Free space on /data in bytes (`df -k /data`).
+pub fn get_free_storage(device_id: &str) -> Option<u64> {
+    let output = run_command(&["-s", device_id, "shell", "df", "-k", "/data"]).ok()?;
+    // Filesystem 1K-blocks Used Available Use% Mounted on
+    let line = output.lines().rev().find(|l| !l.trim().is_empty())?;
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    parts.get(3)?.parse::<u64>().ok().map(|kb| kb * 1024)
+}
+
+/// Returns the on-device path of the installed base APK, if the package is installed.
+pub fn installed_base_apk(device_id: &str, package_name: &str) -> Option<String> {
+    let output = run_command(&["-s", device_id, "shell", "pm", "path", package_name]).ok()?;
+    let paths: Vec<&str> = output
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix("package:"))
+        .collect();
+    paths
+        .iter()
+        .find(|p| p.ends_with("/base.apk"))
+        .or(paths.first())
+        .map(|p| p.to_string())
+}
+
+/// Pulls the installed base APK and returns its signing certificate digest.
+fn installed_signing_cert(device_id: &str, package_name: &str, apk_path: &str) -> Option<String> {
+    let temp_dir = env::temp_dir().join("easyadb_preflight");
+    fs::create_dir_all(&temp_dir).ok()?;
+    let local = temp_dir.join(format!("{}.apk", package_name));
+
+    let pulled = run_command(&["-s", device_id, "pull", apk_path, local.to_str()?]);
+    let cert = match pulled {
+        Ok(_) => package_info::signing_cert_sha256(&local),
+        Err(_) => None,
+    };
+    let _ = fs::remove_file(&local);
+    cert
+}
+
+/// SDK level, native ABIs, installed version and signature of the backed up APK.
+fn check_apk(
+    device_id: &str,
+    package_name: &str,
+    details: &PackageDetails,
+    options: &RestoreOptions,
+    report: &mut RestorePreflight,
+) {
+    // 1. SDK level
+    let device_sdk: Option<u32> =
+        package_info::get_prop(device_id, "ro.build.version.sdk").and_then(|s| s.parse().ok());
+    if let (Some(device_sdk), Some(min_sdk)) = (device_sdk, details.min_sdk) {
+        if device_sdk < min_sdk {
+            report.error(
+                "SDK_TOO_LOW",
+                format!(
+                    "App requires Android SDK {} but the device runs SDK {}",
+                    min_sdk, device_sdk
+                ),
+            );
+        }
+    }
+
+    // 2. Native ABIs
+    if !details.native_abis.is_empty() {
+        let device_abis = get_device_abis(device_id);
+        if !device_abis.is_empty() && !details.native_abis.iter().any(|a| device_abis.contains(a)) {
+            report.error(
+                "ABI_MISMATCH",
+                format!(
+                    "Backup contains native code for [{}] but the device supports [{}]",
+                    details.native_abis.join(", "),
+                    device_abis.join(", ")
+                ),
+            );
+        }
+    }
+
+    // 3. Installed version & signature
+    if !package_name.is_empty() {
+        if let Some(installed_apk) = installed_base_apk(device_id, package_name) {
+            let installed = package_info::query_package_details(device_id, package_name);
+
+            if let (Some(installed_code), Some(backup_code)) =
+                (installed.version_code, details.version_code)
+            {
+                if installed_code > backup_code {
+                    let message = format!(
+                        "Installed version ({}) is newer than the backup ({})",
+                        installed.version_name.as_deref().unwrap_or("?"),
+                        details.version_name.as_deref().unwrap_or("?")
+                    );
+                    if options.skip_if_newer_installed {
+                        report.warning("SKIPPED_NEWER_INSTALLED", message);
+                    } else if options.allow_downgrade {
+                        report.warning("VERSION_DOWNGRADE", message);
+                    } else {
+                        report.error("VERSION_DOWNGRADE", message);
+                    }
+                }
+            }
+
+            if let Some(backup_cert) = &details.signing_cert_sha256 {
+                match installed_signing_cert(device_id, package_name, &installed_apk) {
+                    Some(installed_cert) if &installed_cert != backup_cert => report.error(
+                        "SIGNATURE_MISMATCH",
+                        "Installed app is signed with a different certificate. Uninstall it first."
+                            .to_string(),
+                    ),
+                    Some(_) => {}
+                    None => report.warning(
+                        "SIGNATURE_UNKNOWN",
+                        "Could not verify the installed app's signature".to_string(),
+                    ),
+                }
+            }
+        }
+    }
+}
+
+/// Runs the compatibility checks for restoring `backup_path` onto `device_id`.
+/// Checks are limited to the components selected in `options`.
+pub fn preflight_restore(
+    device_id: &str,
+    backup_path: &Path,
+    options: &RestoreOptions,
+) -> Result<RestorePreflight, String> {
+    if !backup_path.exists() {
+        return Err("Backup file not found".to_string());
+    }
+
+    let mut report = RestorePreflight {
+        package_name: None,
+        can_restore: true,
+        errors: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    let manifest = match backup_manifest::read_backup_manifest(backup_path) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            report.error("UNSUPPORTED_FORMAT", e);
+            report.can_restore = false;
+            return Ok(report);
+        }
+    };
+    if manifest.is_legacy() {
+        report.warning(
+            "NO_METADATA",
+            "Backup has no metadata (legacy format). Compatibility can't be verified.".to_string(),
+        );
+        return Ok(report);
+    }
+
+    let package_name = manifest.package_name.clone();
+    let details: PackageDetails = manifest.app;
+    let has_data = manifest.has_data || manifest.has_de_data;
+    report.package_name = Some(package_name.clone()).filter(|p| !p.is_empty());
+
+    let components = &options.components;
+
+    // 1-3 only matter when the APK is (re)installed
+    if components.apk {
+        check_apk(device_id, &package_name, &details, options, &mut report);
+    }
+
+    // 4. Free storage vs backup size
+    let backup_size = fs::metadata(backup_path).map(|m| m.len()).unwrap_or(0);
+    match get_free_storage(device_id) {
+        Some(free) if free < backup_size => report.error(
+            "INSUFFICIENT_STORAGE",
+            format!(
+                "Not enough free space: {} MB needed, {} MB available",
+                backup_size / 1024 / 1024,
+                free / 1024 / 1024
+            ),
+        ),
+        // Data/OBB archives are staged on /sdcard before extraction
+        Some(free) if free < backup_size * 2 => report.warning(
+            "LOW_STORAGE",
+            format!(
+                "Free space is low ({} MB). Restore may fail while staging files.",
+                free / 1024 / 1024
+            ),
+        ),
+        Some(_) => {}
+        None => report.warning(
+            "STORAGE_UNKNOWN",
+            "Could not determine free storage".to_string(),
+        ),
+    }
+
+    // 5. Root for data
+    if components.data && has_data && !adb::is_device_rooted(device_id) {
+        report.warning(
+            "NO_ROOT_FOR_DATA",
+            "Backup contains app data but the device is not rooted. Only the APK will be restored."
+                .to_string(),
+        );
+    }
+
+    report.can_restore = report.errors.is_empty();
+    Ok(report)
+}
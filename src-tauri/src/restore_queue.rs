@@ -0,0 +1,341 @@
+//! Bulk restore: many backups onto one device in a single job.
+//!
+//! The requested backups (paths and/or package names looked up in the
+//! catalog) are reduced to the newest backup per package. Each app then goes
+//! through its conflict policy before `restore_package` runs. Progress is
+//! reported through `bulk-restore-*` events; a cancelled job stops after the
+//! app currently being restored.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::adb::{self, RestoreOptions, RestoreReport};
+use crate::backup_catalog::{self, BackupCatalog, CatalogEntry, CatalogQuery};
+use crate::package_info;
+
+/// What to do when the app is already installed
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Leave installed apps alone
+    SkipIfInstalled,
+    /// Always restore over the installed app
+    #[default]
+    Replace,
+    /// Restore only if the backup's versionCode is higher than the installed one
+    ReplaceIfNewer,
+    /// Keep the installed APK, restore data/OBB/permissions only
+    DataOnly,
+}
+
+/// Toplu restore isteği
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct BulkRestoreRequest {
+    pub device_id: String,
+    /// Backup files; several backups of one package resolve to the newest
+    pub backups: Vec<String>,
+    /// Packages resolved to their newest backup in the catalog
+    pub packages: Vec<String>,
+    pub default_policy: ConflictPolicy,
+    pub policies: HashMap<String, ConflictPolicy>,
+    pub options: RestoreOptions,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PlannedRestore {
+    /// Empty for legacy backups without metadata
+    pub package_name: String,
+    pub backup_path: String,
+    pub backup_date: String,
+    pub policy: ConflictPolicy,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreOutcome {
+    Pending,
+    Restored,
+    Skipped,
+    Failed,
+    Cancelled,
+}
+
+/// Tek bir uygulamanın toplu restore sonucu
+#[derive(Serialize, Debug, Clone)]
+pub struct AppRestoreResult {
+    pub package_name: String,
+    pub backup_path: String,
+    pub policy: ConflictPolicy,
+    pub outcome: RestoreOutcome,
+    pub message: String,
+    pub report: Option<RestoreReport>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BulkRestoreJob {
+    pub id: String,
+    pub device_id: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub cancelled: bool,
+    pub results: Vec<AppRestoreResult>,
+}
+
+#[derive(Serialize, Clone)]
+struct BulkRestoreProgress {
+    job_id: String,
+    package_name: String,
+    current: usize,
+    total: usize,
+}
+
+/// Bulk restore job yöneticisi
+pub struct RestoreQueue {
+    jobs: Mutex<HashMap<String, BulkRestoreJob>>,
+    cancel_requested: Mutex<HashSet<String>>,
+}
+
+impl RestoreQueue {
+    pub fn new() -> Self {
+        RestoreQueue {
+            jobs: Mutex::new(HashMap::new()),
+            cancel_requested: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn get(&self, job_id: &str) -> Option<BulkRestoreJob> {
+        self.jobs.lock().unwrap().get(job_id).cloned()
+    }
+
+    pub fn cancel(&self, job_id: &str) -> bool {
+        if !self.jobs.lock().unwrap().contains_key(job_id) {
+            return false;
+        }
+        self.cancel_requested
+            .lock()
+            .unwrap()
+            .insert(job_id.to_string());
+        true
+    }
+
+    fn is_cancelled(&self, job_id: &str) -> bool {
+        self.cancel_requested.lock().unwrap().contains(job_id)
+    }
+
+    fn update(&self, job_id: &str, f: impl FnOnce(&mut BulkRestoreJob)) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(job_id) {
+            f(job);
+        }
+    }
+}
+
+/// Resolves the request to the newest backup per package, sorted by package name.
+pub fn plan(catalog: &BackupCatalog, request: &BulkRestoreRequest) -> Vec<PlannedRestore> {
+    let mut latest: HashMap<String, CatalogEntry> = HashMap::new();
+    let mut legacy: Vec<CatalogEntry> = Vec::new();
+
+    let mut candidates: Vec<CatalogEntry> = request
+        .backups
+        .iter()
+        .filter_map(|p| backup_catalog::read_entry(Path::new(p)).ok())
+        .collect();
+    for package_name in &request.packages {
+        let query = CatalogQuery {
+            package_name: Some(package_name.clone()),
+            ..Default::default()
+        };
+        candidates.extend(catalog.query(&query).into_iter().take(1));
+    }
+
+    for entry in candidates {
+        let Some(package_name) = entry.package_name.clone() else {
+            if !legacy.iter().any(|e| e.path == entry.path) {
+                legacy.push(entry);
+            }
+            continue;
+        };
+        match latest.get(&package_name) {
+            Some(current) if current.backup_timestamp >= entry.backup_timestamp => {}
+            _ => {
+                latest.insert(package_name, entry);
+            }
+        }
+    }
+
+    let mut planned: Vec<PlannedRestore> = latest
+        .into_values()
+        .chain(legacy)
+        .map(|entry| {
+            let package_name = entry.package_name.unwrap_or_default();
+            PlannedRestore {
+                policy: request
+                    .policies
+                    .get(&package_name)
+                    .copied()
+                    .unwrap_or(request.default_policy),
+                package_name,
+                backup_path: entry.path,
+                backup_date: entry.backup_date,
+            }
+        })
+        .collect();
+    planned.sort_by(|a, b| a.package_name.cmp(&b.package_name));
+    planned
+}
+
+/// Applies the conflict policy and restores one app.
+fn restore_one(
+    device_id: &str,
+    item: &PlannedRestore,
+    options: &RestoreOptions,
+) -> (RestoreOutcome, String, Option<RestoreReport>) {
+    let installed = if item.package_name.is_empty() {
+        None
+    } else {
+        Some(package_info::query_package_details(
+            device_id,
+            &item.package_name,
+        ))
+        .filter(|d| d.version_code.is_some())
+    };
+
+    let mut options = options.clone();
+    match item.policy {
+        ConflictPolicy::SkipIfInstalled if installed.is_some() => {
+            return (
+                RestoreOutcome::Skipped,
+                "Already installed".to_string(),
+                None,
+            );
+        }
+        ConflictPolicy::ReplaceIfNewer => {
+            let backup_code = backup_catalog::read_entry(Path::new(&item.backup_path))
+                .ok()
+                .and_then(|e| e.app)
+                .and_then(|app| app.version_code);
+            let installed_code = installed.as_ref().and_then(|d| d.version_code);
+            if let (Some(backup), Some(current)) = (backup_code, installed_code) {
+                if backup <= current {
+                    return (
+                        RestoreOutcome::Skipped,
+                        format!(
+                            "Installed version ({}) is not older than the backup ({})",
+                            current, backup
+                        ),
+                        None,
+                    );
+                }
+            }
+        }
+        ConflictPolicy::DataOnly => {
+            if installed.is_none() {
+                return (
+                    RestoreOutcome::Skipped,
+                    "Not installed; data-only restore needs the app".to_string(),
+                    None,
+                );
+            }
+            options.components.apk = false;
+        }
+        _ => {}
+    }
+
+    match adb::restore_package(device_id, PathBuf::from(&item.backup_path), &options) {
+        Ok(report) => {
+            let outcome = if report.applied.is_empty() {
+                RestoreOutcome::Skipped
+            } else {
+                RestoreOutcome::Restored
+            };
+            (outcome, report.message.clone(), Some(report))
+        }
+        Err(e) => (RestoreOutcome::Failed, e, None),
+    }
+}
+
+/// Starts a bulk restore on a worker thread and returns the job id.
+pub fn start(app: &AppHandle, request: BulkRestoreRequest) -> Result<String, String> {
+    let planned = plan(&app.state::<BackupCatalog>(), &request);
+    if planned.is_empty() {
+        return Err("No backups to restore".to_string());
+    }
+
+    let now = chrono::Local::now();
+    let job = BulkRestoreJob {
+        id: format!("restore_{}", now.format("%Y%m%d_%H%M%S%3f")),
+        device_id: request.device_id.clone(),
+        started_at: now.to_rfc3339(),
+        finished_at: None,
+        cancelled: false,
+        results: planned
+            .iter()
+            .map(|p| AppRestoreResult {
+                package_name: p.package_name.clone(),
+                backup_path: p.backup_path.clone(),
+                policy: p.policy,
+                outcome: RestoreOutcome::Pending,
+                message: String::new(),
+                report: None,
+            })
+            .collect(),
+    };
+    let job_id = job.id.clone();
+    app.state::<RestoreQueue>()
+        .jobs
+        .lock()
+        .unwrap()
+        .insert(job_id.clone(), job);
+
+    let app = app.clone();
+    let id = job_id.clone();
+    thread::spawn(move || {
+        let queue = app.state::<RestoreQueue>();
+        let total = planned.len();
+
+        for (i, item) in planned.iter().enumerate() {
+            if queue.is_cancelled(&id) {
+                queue.update(&id, |job| {
+                    job.cancelled = true;
+                    for result in job.results.iter_mut().skip(i) {
+                        result.outcome = RestoreOutcome::Cancelled;
+                    }
+                });
+                break;
+            }
+
+            let _ = app.emit(
+                "bulk-restore-progress",
+                BulkRestoreProgress {
+                    job_id: id.clone(),
+                    package_name: item.package_name.clone(),
+                    current: i + 1,
+                    total,
+                },
+            );
+
+            let (outcome, message, report) =
+                restore_one(&request.device_id, item, &request.options);
+            queue.update(&id, |job| {
+                let result = &mut job.results[i];
+                result.outcome = outcome;
+                result.message = message;
+                result.report = report;
+            });
+        }
+
+        queue.update(&id, |job| {
+            job.finished_at = Some(chrono::Local::now().to_rfc3339());
+        });
+        queue.cancel_requested.lock().unwrap().remove(&id);
+        if let Some(job) = queue.get(&id) {
+            let _ = app.emit("bulk-restore-finished", job);
+        }
+    });
+
+    Ok(job_id)
+}
@@ -0,0 +1,261 @@
+//! Backup retention rules evaluated over the backup catalog.
+//!
+//! Rules (per package, falling back to the global rule):
+//! * `keep_last`: keep the N newest backups
+//! * `keep_weekly_weeks`: keep the newest backup of each week for the last M weeks
+//! * `max_total_size`: delete the oldest backups until the total fits
+//!
+//! The newest backup of a package is never deleted, and backups without
+//! metadata (unknown package) are left alone.
+
+use chrono::Datelike;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::backup_catalog::{BackupCatalog, CatalogEntry, CatalogQuery};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct RetentionRule {
+    pub keep_last: Option<usize>,
+    pub keep_weekly_weeks: Option<u32>,
+    /// Bytes
+    pub max_total_size: Option<u64>,
+}
+
+impl RetentionRule {
+    fn is_empty(&self) -> bool {
+        self.keep_last.is_none()
+            && self.keep_weekly_weeks.is_none()
+            && self.max_total_size.is_none()
+    }
+}
+
+/// Saklama politikası (retention_policy.json)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// Applied to packages without their own rule. Its `max_total_size` also
+    /// caps the total size of all backups.
+    pub global: RetentionRule,
+    pub per_package: HashMap<String, RetentionRule>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PlannedDeletion {
+    pub path: String,
+    pub package_name: String,
+    pub backup_date: String,
+    pub size: u64,
+    /// keep_last / keep_weekly / max_total_size / global_max_total_size
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RetentionPlan {
+    pub delete: Vec<PlannedDeletion>,
+    pub keep_count: usize,
+    pub freed_bytes: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RetentionReport {
+    pub deleted: Vec<String>,
+    pub failed: Vec<String>,
+    pub freed_bytes: u64,
+}
+
+/// Retention policy yöneticisi
+pub struct RetentionPolicies {
+    file_path: PathBuf,
+    policy: Mutex<RetentionPolicy>,
+}
+
+impl RetentionPolicies {
+    pub fn new(app_handle: &AppHandle) -> Self {
+        let app_dir = app_handle
+            .path()
+            .app_data_dir()
+            .unwrap_or_else(|_| PathBuf::from("."));
+        if !app_dir.exists() {
+            let _ = fs::create_dir_all(&app_dir);
+        }
+
+        let file_path = app_dir.join("retention_policy.json");
+        let policy = fs::read_to_string(&file_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        RetentionPolicies {
+            file_path,
+            policy: Mutex::new(policy),
+        }
+    }
+
+    pub fn get(&self) -> RetentionPolicy {
+        self.policy.lock().unwrap().clone()
+    }
+
+    pub fn set(&self, policy: RetentionPolicy) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(&policy).map_err(|e| e.to_string())?;
+        fs::write(&self.file_path, json).map_err(|e| e.to_string())?;
+        *self.policy.lock().unwrap() = policy;
+        Ok(())
+    }
+}
+
+fn week_key(entry: &CatalogEntry) -> Option<(i32, u32)> {
+    let date = chrono::DateTime::parse_from_rfc3339(&entry.backup_date).ok()?;
+    let week = date.iso_week();
+    Some((week.year(), week.week()))
+}
+
+/// Computes which backups the policy would delete. Nothing is touched.
+pub fn plan(entries: &[CatalogEntry], policy: &RetentionPolicy) -> RetentionPlan {
+    let now = chrono::Local::now().timestamp();
+
+    let mut by_package: HashMap<&str, Vec<&CatalogEntry>> = HashMap::new();
+    for entry in entries {
+        if let Some(pkg) = entry.package_name.as_deref() {
+            by_package.entry(pkg).or_default().push(entry);
+        }
+    }
+
+    let mut delete: Vec<PlannedDeletion> = Vec::new();
+    let mut protected: HashSet<&str> = HashSet::new();
+    let mut survivors: Vec<&CatalogEntry> = Vec::new();
+
+    for (pkg, mut backups) in by_package {
+        backups.sort_by_key(|e| std::cmp::Reverse(e.backup_timestamp));
+        let rule = policy.per_package.get(pkg).unwrap_or(&policy.global);
+        protected.insert(backups[0].path.as_str());
+
+        if rule.is_empty() {
+            survivors.extend(backups);
+            continue;
+        }
+
+        // 1. keep_last / keep_weekly: a backup survives if any count rule keeps it
+        let count_rules = rule.keep_last.is_some() || rule.keep_weekly_weeks.is_some();
+        let mut kept: Vec<&CatalogEntry> = Vec::new();
+        let mut seen_weeks = HashSet::new();
+        for (i, backup) in backups.iter().enumerate() {
+            let by_last = rule.keep_last.is_some_and(|n| i < n);
+            let by_week = rule.keep_weekly_weeks.is_some_and(|weeks| {
+                let in_window = now - backup.backup_timestamp < weeks as i64 * 7 * 24 * 3600;
+                // Newest first, so the first backup seen for a week is the one kept
+                in_window && week_key(backup).is_some_and(|k| seen_weeks.insert(k))
+            });
+
+            if !count_rules || i == 0 || by_last || by_week {
+                kept.push(backup);
+            } else {
+                let reason = if rule.keep_last.is_some() {
+                    "keep_last"
+                } else {
+                    "keep_weekly"
+                };
+                delete.push(planned(backup, pkg, reason));
+            }
+        }
+
+        // 2. Per-package size cap, oldest first
+        if let Some(max) = rule.max_total_size {
+            let mut total: u64 = kept.iter().map(|e| e.size).sum();
+            while total > max && kept.len() > 1 {
+                let oldest = kept.pop().unwrap();
+                total -= oldest.size;
+                delete.push(planned(oldest, pkg, "max_total_size"));
+            }
+        }
+        survivors.extend(kept);
+    }
+
+    // 3. Global size cap across every package
+    if let Some(max) = policy.global.max_total_size {
+        let unknown_size: u64 = entries
+            .iter()
+            .filter(|e| e.package_name.is_none())
+            .map(|e| e.size)
+            .sum();
+        let mut total = unknown_size + survivors.iter().map(|e| e.size).sum::<u64>();
+        survivors.sort_by_key(|e| e.backup_timestamp);
+        for backup in &survivors {
+            if total <= max {
+                break;
+            }
+            if protected.contains(backup.path.as_str()) {
+                continue;
+            }
+            total -= backup.size;
+            let pkg = backup.package_name.as_deref().unwrap_or_default();
+            delete.push(planned(backup, pkg, "global_max_total_size"));
+        }
+    }
+
+    delete.sort_by(|a, b| {
+        a.package_name
+            .cmp(&b.package_name)
+            .then(a.backup_date.cmp(&b.backup_date))
+    });
+    let freed_bytes = delete.iter().map(|d| d.size).sum();
+    RetentionPlan {
+        keep_count: entries.len() - delete.len(),
+        delete,
+        freed_bytes,
+    }
+}
+
+fn planned(entry: &CatalogEntry, package_name: &str, reason: &str) -> PlannedDeletion {
+    PlannedDeletion {
+        path: entry.path.clone(),
+        package_name: package_name.to_string(),
+        backup_date: entry.backup_date.clone(),
+        size: entry.size,
+        reason: reason.to_string(),
+    }
+}
+
+/// Dry run over the current catalog.
+pub fn preview(catalog: &BackupCatalog, policy: &RetentionPolicy) -> RetentionPlan {
+    catalog.refresh();
+    plan(&catalog.query(&CatalogQuery::default()), policy)
+}
+
+/// Deletes the backups the user confirmed from `preview`, through the
+/// catalog's delete path. Aborts without deleting anything if the plan no
+/// longer matches `confirmed` (e.g. a backup was made in the meantime).
+pub fn apply(
+    catalog: &BackupCatalog,
+    policy: &RetentionPolicy,
+    confirmed: &[String],
+) -> Result<RetentionReport, String> {
+    let plan = preview(catalog, policy);
+    let planned: HashSet<&str> = plan.delete.iter().map(|d| d.path.as_str()).collect();
+    let confirmed: HashSet<&str> = confirmed.iter().map(|p| p.as_str()).collect();
+    if planned != confirmed {
+        return Err("Backups changed since the preview; preview again before deleting".to_string());
+    }
+
+    let mut report = RetentionReport {
+        deleted: Vec::new(),
+        failed: Vec::new(),
+        freed_bytes: 0,
+    };
+
+    for item in plan.delete {
+        match catalog.delete_backup(Path::new(&item.path)) {
+            Ok(()) => {
+                report.freed_bytes += item.size;
+                report.deleted.push(item.path);
+            }
+            Err(e) => report.failed.push(format!("{}: {}", item.path, e)),
+        }
+    }
+    Ok(report)
+}
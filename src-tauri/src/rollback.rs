@@ -0,0 +1,180 @@
+//! Pre-restore snapshot of the installed app, used to undo a failed restore.
+//!
+//! Before `restore_package` touches the device, the currently installed APKs
+//! (when the APK is going to be reinstalled) and the root data dirs (when data
+//! is going to be overwritten) are copied
+//! to the PC. If a later step fails, the previous APKs are reinstalled with
+//! downgrade allowed and the old data is put back; an app that wasn't
+//! installed before is removed again.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::adb::{self, run_command, RestoreOptions};
+use crate::package_info;
+use crate::root_data::{self, RootDataBackup};
+
+/// Kurulu uygulamanın restore öncesi kopyası
+pub struct Snapshot {
+    package_name: String,
+    user: u32,
+    dir: PathBuf,
+    was_installed: bool,
+    /// The restore reinstalls the APK, so the APK part is rolled back too
+    with_apk: bool,
+    version_name: Option<String>,
+    apks: Vec<PathBuf>,
+    data: RootDataBackup,
+    /// Why the data couldn't be saved, reported again on rollback
+    data_error: Option<String>,
+}
+
+/// APK paths of the installed package (`pm path` lists base + splits).
+fn installed_apk_paths(device_id: &str, package_name: &str) -> Vec<String> {
+    run_command(&["-s", device_id, "shell", "pm", "path", package_name])
+        .map(|out| {
+            out.lines()
+                .filter_map(|l| l.trim().strip_prefix("package:"))
+                .map(|p| p.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Copies the installed app to a temp folder. Never fails: whatever could be
+/// saved is what can be rolled back.
+pub fn take(
+    device_id: &str,
+    package_name: &str,
+    user: u32,
+    with_apk: bool,
+    with_data: bool,
+) -> Snapshot {
+    let dir = env::temp_dir().join("easyadb_snapshot").join(package_name);
+    let _ = fs::remove_dir_all(&dir);
+    let apks_dir = dir.join("apks");
+    let _ = fs::create_dir_all(&apks_dir);
+
+    let remote_apks = installed_apk_paths(device_id, package_name);
+    let mut snapshot = Snapshot {
+        package_name: package_name.to_string(),
+        user,
+        was_installed: !remote_apks.is_empty(),
+        with_apk,
+        version_name: None,
+        apks: Vec::new(),
+        data: RootDataBackup::default(),
+        data_error: None,
+        dir,
+    };
+    if !snapshot.was_installed {
+        return snapshot;
+    }
+
+    snapshot.version_name =
+        package_info::query_package_details(device_id, package_name).version_name;
+
+    // Data-only restores leave the installed APK alone; nothing to save
+    let remote_apks = if with_apk { remote_apks } else { Vec::new() };
+    for (i, remote) in remote_apks.iter().enumerate() {
+        // Index prefix keeps the pulled names unique and the base APK first
+        let file_name = remote.rsplit('/').next().unwrap_or("base.apk");
+        let local = apks_dir.join(format!("{}_{}", i, file_name));
+        if run_command(&["-s", device_id, "pull", remote, &local.to_string_lossy()]).is_ok() {
+            snapshot.apks.push(local);
+        } else {
+            // An incomplete split set can't be reinstalled
+            snapshot.apks.clear();
+            break;
+        }
+    }
+
+    let rooted = run_command(&["-s", device_id, "shell", "su", "-c", "id"]).is_ok();
+    if with_data && rooted {
+        match root_data::backup(device_id, package_name, user, &snapshot.dir) {
+            Ok(data) => snapshot.data = data,
+            Err(e) => {
+                log::warn!(
+                    "Pre-restore data snapshot failed for {}: {}",
+                    package_name,
+                    e
+                );
+                snapshot.data_error = Some(e);
+            }
+        }
+    }
+
+    snapshot
+}
+
+impl Snapshot {
+    /// Puts the snapshot back. Returns a summary of what was rolled back / failed.
+    pub fn rollback(&self, device_id: &str, options: &RestoreOptions) -> String {
+        let mut rolled_back = Vec::new();
+        let mut failed = Vec::new();
+
+        if !self.with_apk {
+            // The APK wasn't touched
+        } else if !self.was_installed {
+            // The failed step may have come before the install
+            if installed_apk_paths(device_id, &self.package_name).is_empty() {
+                return "Nothing to roll back".to_string();
+            }
+            match run_command(&["-s", device_id, "uninstall", &self.package_name]) {
+                Ok(_) => rolled_back.push("apk (removed)".to_string()),
+                Err(e) => failed.push(format!("apk: {}", e)),
+            }
+        } else if self.apks.is_empty() {
+            failed.push("apk: previous version could not be saved".to_string());
+        } else {
+            let reinstall = RestoreOptions {
+                allow_downgrade: true,
+                allow_test: true,
+                user: options.user,
+                ..Default::default()
+            };
+            match adb::install_apks(device_id, &self.apks, &reinstall) {
+                Ok(out) if out.contains("Success") => rolled_back.push(format!(
+                    "apk ({})",
+                    self.version_name.as_deref().unwrap_or("previous version")
+                )),
+                Ok(out) => failed.push(format!("apk: {}", out.trim())),
+                Err(e) => failed.push(format!("apk: {}", e)),
+            }
+        }
+
+        if let Some(e) = &self.data_error {
+            failed.push(format!("data: not saved before restore ({})", e));
+        } else if self.was_installed && (self.data.has_ce || self.data.has_de) {
+            let ce = self.dir.join(root_data::CE_ARCHIVE);
+            let de = self.dir.join(root_data::DE_ARCHIVE);
+            match root_data::restore(
+                device_id,
+                &self.package_name,
+                self.user,
+                Some(ce.as_path()).filter(|_| self.data.has_ce),
+                Some(de.as_path()).filter(|_| self.data.has_de),
+            ) {
+                Ok(()) => rolled_back.push("data".to_string()),
+                Err(e) => failed.push(format!("data: {}", e)),
+            }
+        }
+
+        match (rolled_back.is_empty(), failed.is_empty()) {
+            (true, true) => "Nothing to roll back".to_string(),
+            (false, true) => format!("Rolled back: {}", rolled_back.join(", ")),
+            (true, false) => format!("Rollback failed: {}", failed.join("; ")),
+            (false, false) => format!(
+                "Rolled back: {}. Rollback failed: {}",
+                rolled_back.join(", "),
+                failed.join("; ")
+            ),
+        }
+    }
+
+    /// Deletes the local copy.
+    pub fn discard(self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
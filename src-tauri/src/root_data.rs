@@ -0,0 +1,261 @@
+//! Root backup / restore of an app's private data.
+//!
+//! Android keeps two data dirs per user:
+//! * credential-encrypted (CE): `/data/user/<N>/<pkg>` (`/data/data` is user 0)
+//! * device-encrypted (DE): `/data/user_de/<N>/<pkg>` (direct boot data)
+//!
+//! Each is archived separately with the package folder as the tar root. On
+//! restore the app is stopped first, old contents are cleared (except the
+//! `lib` symlink, which points into the current install), and ownership is
+//! set from the app's real UID instead of whatever the extracted files carry.
+
+use std::path::Path;
+
+use crate::adb::run_command;
+
+/// Per-user UID range (`UserHandle.PER_USER_RANGE`)
+const PER_USER_RANGE: u32 = 100_000;
+/// First app id (`AID_APP_START`)
+const APP_START: u32 = 10_000;
+/// `cache/` and `code_cache/` are group-owned by
+/// `AID_CACHE_GID_START + (appId - AID_APP_START)` (`multiuser_get_cache_gid`)
+const CACHE_GID_START: u32 = 20_000;
+
+pub const CE_ARCHIVE: &str = "data.tar.gz";
+pub const DE_ARCHIVE: &str = "data_de.tar.gz";
+
+/// Which data dirs ended up in the backup
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RootDataBackup {
+    pub has_ce: bool,
+    pub has_de: bool,
+}
+
+fn su(device_id: &str, cmd: &str) -> Result<String, String> {
+    run_command(&["-s", device_id, "shell", "su", "-c", cmd])
+}
+
+pub fn ce_dir(user: u32, package_name: &str) -> String {
+    format!("/data/user/{}/{}", user, package_name)
+}
+
+pub fn de_dir(user: u32, package_name: &str) -> String {
+    format!("/data/user_de/{}/{}", user, package_name)
+}
+
+/// Parses `pm list packages -U` output (`package:com.foo uid:10123` or
+/// `uid:10123,1010123` with several users) into the app id.
+pub fn parse_pm_uid(output: &str, package_name: &str) -> Option<u32> {
+    output.lines().find_map(|line| {
+        let rest = line.trim().strip_prefix("package:")?;
+        let (name, uid) = rest.split_once(" uid:")?;
+        if name != package_name {
+            return None;
+        }
+        let uid: u32 = uid.split(',').next()?.trim().parse().ok()?;
+        Some(uid % PER_USER_RANGE)
+    })
+}
+
+/// Parses `userId=10123` (older) or `appId=10123` from `dumpsys package`.
+fn parse_dumpsys_uid(output: &str) -> Option<u32> {
+    output.lines().find_map(|line| {
+        let line = line.trim();
+        let value = line
+            .strip_prefix("userId=")
+            .or_else(|| line.strip_prefix("appId="))?;
+        let uid: u32 = value.split_whitespace().next()?.parse().ok()?;
+        Some(uid % PER_USER_RANGE)
+    })
+}
+
+/// App id (UID without the user part) as known to the package manager.
+pub fn app_id(device_id: &str, package_name: &str) -> Result<u32, String> {
+    if let Ok(output) = run_command(&[
+        "-s",
+        device_id,
+        "shell",
+        "pm",
+        "list",
+        "packages",
+        "-U",
+        package_name,
+    ]) {
+        if let Some(id) = parse_pm_uid(&output, package_name) {
+            return Ok(id);
+        }
+    }
+
+    let output = run_command(&["-s", device_id, "shell", "dumpsys", "package", package_name])?;
+    parse_dumpsys_uid(&output).ok_or_else(|| format!("Could not resolve UID of {}", package_name))
+}
+
+pub fn force_stop(device_id: &str, package_name: &str, user: u32) {
+    let user = user.to_string();
+    let _ = run_command(&[
+        "-s",
+        device_id,
+        "shell",
+        "am",
+        "force-stop",
+        "--user",
+        &user,
+        package_name,
+    ]);
+}
+
+fn dir_exists(device_id: &str, dir: &str) -> bool {
+    su(device_id, &format!("[ -d '{}' ] && echo yes", dir))
+        .map(|out| out.trim() == "yes")
+        .unwrap_or(false)
+}
+
+/// Archives one data dir to `local_path`. Cache contents and the `lib` symlink are left out.
+fn backup_dir(
+    device_id: &str,
+    package_name: &str,
+    dir: &str,
+    local_path: &Path,
+) -> Result<bool, String> {
+    if !dir_exists(device_id, dir) {
+        return Ok(false);
+    }
+
+    let parent = dir.trim_end_matches(package_name).trim_end_matches('/');
+    let remote = format!(
+        "/sdcard/easyadb_{}_{}",
+        package_name,
+        local_path.file_name().unwrap_or_default().to_string_lossy()
+    );
+    let tar_cmd = format!(
+        "tar -czf '{remote}' -C '{parent}' --exclude='{pkg}/lib' --exclude='{pkg}/cache/*' --exclude='{pkg}/code_cache/*' '{pkg}'",
+        remote = remote,
+        parent = parent,
+        pkg = package_name
+    );
+    su(device_id, &tar_cmd)?;
+
+    let pulled = run_command(&[
+        "-s",
+        device_id,
+        "pull",
+        &remote,
+        &local_path.to_string_lossy(),
+    ]);
+    let _ = run_command(&["-s", device_id, "shell", "rm", "-f", &remote]);
+    pulled?;
+    Ok(local_path.exists())
+}
+
+/// Backs up the CE and DE data dirs of `user` into `local_dir`
+/// (`data.tar.gz` / `data_de.tar.gz`). The app is stopped for a consistent copy.
+pub fn backup(
+    device_id: &str,
+    package_name: &str,
+    user: u32,
+    local_dir: &Path,
+) -> Result<RootDataBackup, String> {
+    force_stop(device_id, package_name, user);
+
+    Ok(RootDataBackup {
+        has_ce: backup_dir(
+            device_id,
+            package_name,
+            &ce_dir(user, package_name),
+            &local_dir.join(CE_ARCHIVE),
+        )?,
+        has_de: backup_dir(
+            device_id,
+            package_name,
+            &de_dir(user, package_name),
+            &local_dir.join(DE_ARCHIVE),
+        )?,
+    })
+}
+
+/// Replaces one data dir with the archive contents and fixes owner, cache GIDs and labels.
+fn restore_dir(
+    device_id: &str,
+    package_name: &str,
+    dir: &str,
+    archive: &Path,
+    uid: u32,
+    cache_gid: u32,
+) -> Result<(), String> {
+    let parent = dir.trim_end_matches(package_name).trim_end_matches('/');
+    let remote = format!(
+        "/data/local/tmp/easyadb_restore_{}",
+        archive.file_name().unwrap_or_default().to_string_lossy()
+    );
+
+    run_command(&["-s", device_id, "push", &archive.to_string_lossy(), &remote])?;
+
+    let restore_cmd = [
+        format!("mkdir -p '{}'", dir),
+        // Keep `lib`: it links to the native libs of the currently installed APK
+        format!(
+            "find '{}' -mindepth 1 -maxdepth 1 ! -name lib -exec rm -rf {{}} +",
+            dir
+        ),
+        format!(
+            "tar -xzf '{}' -C '{}' --exclude='{}/lib'",
+            remote, parent, package_name
+        ),
+        format!("mkdir -p '{0}/cache' '{0}/code_cache'", dir),
+        format!(
+            "find '{0}' -path '{0}/lib' -prune -o -exec chown -h {1}:{1} {{}} +",
+            dir, uid
+        ),
+        format!(
+            "chown -R {}:{} '{2}/cache' '{2}/code_cache'",
+            uid, cache_gid, dir
+        ),
+        format!("restorecon -RF '{}'", dir),
+    ]
+    .join(" && ");
+
+    let result = su(device_id, &restore_cmd);
+    let _ = su(device_id, &format!("rm -f '{}'", remote));
+    result.map(|_| ())
+}
+
+/// Restores the CE and/or DE archives for `user`. The app must already be installed.
+pub fn restore(
+    device_id: &str,
+    package_name: &str,
+    user: u32,
+    ce_archive: Option<&Path>,
+    de_archive: Option<&Path>,
+) -> Result<(), String> {
+    let app_id = app_id(device_id, package_name)?;
+    let uid = user * PER_USER_RANGE + app_id;
+    // Apps below AID_APP_START have no cache GID; keep their own group
+    let cache_gid = match app_id.checked_sub(APP_START) {
+        Some(offset) => user * PER_USER_RANGE + CACHE_GID_START + offset,
+        None => uid,
+    };
+
+    force_stop(device_id, package_name, user);
+
+    if let Some(archive) = ce_archive {
+        restore_dir(
+            device_id,
+            package_name,
+            &ce_dir(user, package_name),
+            archive,
+            uid,
+            cache_gid,
+        )?;
+    }
+    if let Some(archive) = de_archive {
+        restore_dir(
+            device_id,
+            package_name,
+            &de_dir(user, package_name),
+            archive,
+            uid,
+            cache_gid,
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,389 @@
+//! Split APK analysis and device-aware selection.
+//!
+//! Config splits are identified from the file name (`split_config.arm64_v8a.apk`,
+//! `config.xxhdpi.apk`) and, when the name doesn't tell, from the `split`
+//! attribute of the binary AndroidManifest.xml. Only the splits matching the
+//! target device's ABI, screen density and locales are installed.
+
+use serde::Serialize;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::adb::run_command;
+use crate::package_info;
+use crate::restore_check;
+
+const ABIS: &[&str] = &[
+    "armeabi",
+    "armeabi_v7a",
+    "arm64_v8a",
+    "x86",
+    "x86_64",
+    "mips",
+    "mips64",
+    "riscv64",
+];
+
+/// Density buckets used by bundletool (`config.<bucket>`)
+const DENSITIES: &[(&str, u32)] = &[
+    ("ldpi", 120),
+    ("mdpi", 160),
+    ("tvdpi", 213),
+    ("hdpi", 240),
+    ("xhdpi", 320),
+    ("xxhdpi", 480),
+    ("xxxhdpi", 640),
+];
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum SplitKind {
+    Base,
+    /// Device ABI in `ro.product.cpu.abilist` form (e.g. "arm64-v8a")
+    Abi(String),
+    Density(u32),
+    /// Language code (e.g. "en", "pt")
+    Language(String),
+    /// Feature or otherwise unknown split; always installed
+    Other,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SplitApk {
+    pub file_name: String,
+    /// Split name from the manifest / file name (None for the base APK)
+    pub split_name: Option<String>,
+    pub kind: SplitKind,
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+/// Hedef cihazın split seçimi için özellikleri
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct DeviceSplitProfile {
+    pub abis: Vec<String>,
+    pub density_dpi: Option<u32>,
+    /// Language codes in preference order (e.g. ["tr", "en"])
+    pub languages: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SplitSelection {
+    pub selected: Vec<PathBuf>,
+    /// File names of splits left out for this device
+    pub excluded: Vec<String>,
+}
+
+/// Classifies a `config.<qualifier>` split name.
+fn classify_config(split_name: &str) -> SplitKind {
+    let Some(qualifier) = split_name.strip_prefix("config.") else {
+        return SplitKind::Other;
+    };
+
+    if ABIS.contains(&qualifier) {
+        return SplitKind::Abi(qualifier.replace('_', "-"));
+    }
+    if let Some((_, dpi)) = DENSITIES.iter().find(|(name, _)| *name == qualifier) {
+        return SplitKind::Density(*dpi);
+    }
+    if let Some(dpi) = qualifier
+        .strip_suffix("dpi")
+        .and_then(|n| n.parse::<u32>().ok())
+    {
+        return SplitKind::Density(dpi);
+    }
+    // nodpi / anydpi resources work everywhere
+    if qualifier.ends_with("dpi") {
+        return SplitKind::Other;
+    }
+
+    let language = qualifier.split(['_', '-']).next().unwrap_or(qualifier);
+    if (2..=3).contains(&language.len()) && language.chars().all(|c| c.is_ascii_lowercase()) {
+        return SplitKind::Language(language.to_string());
+    }
+    SplitKind::Other
+}
+
+/// Split name from a file name: "split_config.en.apk" / "config.en.apk" -> "config.en".
+fn split_name_from_file(file_name: &str) -> Option<String> {
+    let stem = file_name.strip_suffix(".apk")?;
+    let stem = stem.strip_prefix("split_").unwrap_or(stem);
+    if stem.starts_with("config.") {
+        Some(stem.to_string())
+    } else {
+        None
+    }
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+/// Reads string `index` from a binary XML string pool chunk.
+fn pool_string(pool: &[u8], index: u32) -> Option<String> {
+    let header_size = read_u16(pool, 2)? as usize;
+    let count = read_u32(pool, 8)?;
+    let utf8 = read_u32(pool, 16)? & (1 << 8) != 0;
+    let strings_start = read_u32(pool, 20)? as usize;
+    if index >= count {
+        return None;
+    }
+    let offset = read_u32(pool, header_size + index as usize * 4)? as usize;
+    let mut pos = strings_start + offset;
+
+    if utf8 {
+        // UTF-16 length, then UTF-8 byte length (each 1 or 2 bytes)
+        let skip_len = |pos: &mut usize| -> Option<usize> {
+            let first = *pool.get(*pos)? as usize;
+            *pos += 1;
+            if first & 0x80 != 0 {
+                let second = *pool.get(*pos)? as usize;
+                *pos += 1;
+                Some(((first & 0x7F) << 8) | second)
+            } else {
+                Some(first)
+            }
+        };
+        skip_len(&mut pos)?;
+        let len = skip_len(&mut pos)?;
+        String::from_utf8(pool.get(pos..pos + len)?.to_vec()).ok()
+    } else {
+        let mut len = read_u16(pool, pos)? as usize;
+        pos += 2;
+        if len & 0x8000 != 0 {
+            len = ((len & 0x7FFF) << 16) | read_u16(pool, pos)? as usize;
+            pos += 2;
+        }
+        let units: Vec<u16> = (0..len)
+            .map(|i| read_u16(pool, pos + i * 2))
+            .collect::<Option<_>>()?;
+        String::from_utf16(&units).ok()
+    }
+}
+
+/// Returns the `split` attribute of the `<manifest>` element in a binary AndroidManifest.xml.
+fn manifest_split_attribute(axml: &[u8]) -> Option<String> {
+    const RES_STRING_POOL_TYPE: u16 = 0x0001;
+    const RES_XML_START_ELEMENT_TYPE: u16 = 0x0102;
+
+    let mut pos = read_u16(axml, 2)? as usize;
+    let mut pool: Option<&[u8]> = None;
+
+    while pos + 8 <= axml.len() {
+        let chunk_type = read_u16(axml, pos)?;
+        let chunk_size = read_u32(axml, pos + 4)? as usize;
+        if chunk_size < 8 {
+            return None;
+        }
+        let chunk = axml.get(pos..pos + chunk_size)?;
+
+        if chunk_type == RES_STRING_POOL_TYPE {
+            pool = Some(chunk);
+        } else if chunk_type == RES_XML_START_ELEMENT_TYPE {
+            // The first element is <manifest>
+            let pool = pool?;
+            let header_size = read_u16(chunk, 2)? as usize;
+            let attr_start = read_u16(chunk, header_size + 8)? as usize;
+            let attr_size = read_u16(chunk, header_size + 10)? as usize;
+            let attr_count = read_u16(chunk, header_size + 12)? as usize;
+
+            for i in 0..attr_count {
+                let attr = header_size + attr_start + i * attr_size;
+                let name = pool_string(pool, read_u32(chunk, attr + 4)?)?;
+                if name == "split" {
+                    return pool_string(pool, read_u32(chunk, attr + 8)?);
+                }
+            }
+            return None;
+        }
+        pos += chunk_size;
+    }
+    None
+}
+
+fn read_manifest_split(path: &Path) -> Option<String> {
+    let file = File::open(path).ok()?;
+    let mut archive = zip::ZipArchive::new(file).ok()?;
+    let mut entry = archive.by_name("AndroidManifest.xml").ok()?;
+    let mut axml = Vec::new();
+    entry.read_to_end(&mut axml).ok()?;
+    manifest_split_attribute(&axml)
+}
+
+/// Classifies every APK of a split set.
+pub fn analyze_splits(apk_paths: &[PathBuf]) -> Vec<SplitApk> {
+    apk_paths
+        .iter()
+        .map(|path| {
+            let file_name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            let split_name = split_name_from_file(&file_name).or_else(|| {
+                if file_name == "base.apk" {
+                    None
+                } else {
+                    read_manifest_split(path)
+                }
+            });
+            let kind = match &split_name {
+                Some(name) => classify_config(name),
+                None if apk_paths.len() == 1 || file_name == "base.apk" => SplitKind::Base,
+                None => SplitKind::Other,
+            };
+
+            SplitApk {
+                file_name,
+                split_name,
+                kind,
+                path: path.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Screen density of the device (`wm density`, override wins).
+fn get_device_density(device_id: &str) -> Option<u32> {
+    let output = run_command(&["-s", device_id, "shell", "wm", "density"]).ok();
+    let parse = |prefix: &str| {
+        output.as_deref().and_then(|out| {
+            out.lines()
+                .find_map(|l| l.trim().strip_prefix(prefix))
+                .and_then(|v| v.trim().parse().ok())
+        })
+    };
+    parse("Override density:")
+        .or_else(|| parse("Physical density:"))
+        .or_else(|| {
+            package_info::get_prop(device_id, "ro.sf.lcd_density").and_then(|v| v.parse().ok())
+        })
+}
+
+/// Device locales as language codes, e.g. "tr-TR,en-US" -> ["tr", "en"].
+fn get_device_languages(device_id: &str) -> Vec<String> {
+    let locales = run_command(&[
+        "-s",
+        device_id,
+        "shell",
+        "settings",
+        "get",
+        "system",
+        "system_locales",
+    ])
+    .ok()
+    .map(|v| v.trim().to_string())
+    .filter(|v| !v.is_empty() && v != "null")
+    .or_else(|| package_info::get_prop(device_id, "persist.sys.locale"))
+    .or_else(|| package_info::get_prop(device_id, "ro.product.locale"))
+    .unwrap_or_default();
+
+    let mut languages = Vec::new();
+    for locale in locales.split(',') {
+        let language = locale.trim().split(['-', '_']).next().unwrap_or("");
+        if !language.is_empty() && !languages.iter().any(|l| l == language) {
+            languages.push(language.to_lowercase());
+        }
+    }
+    languages
+}
+
+pub fn query_device_profile(device_id: &str) -> DeviceSplitProfile {
+    DeviceSplitProfile {
+        abis: restore_check::get_device_abis(device_id),
+        density_dpi: get_device_density(device_id),
+        languages: get_device_languages(device_id),
+    }
+}
+
+/// Picks the splits that match the device.
+///
+/// * ABI: the split for the most preferred device ABI; an error if none matches.
+/// * Density: the closest bucket at or above the device density, else the largest.
+/// * Language: every split matching one of the device languages.
+/// * Base and feature splits are always kept.
+///
+/// Unknown device properties (empty ABI list, no density) keep all splits of that kind.
+pub fn select_splits(
+    splits: &[SplitApk],
+    profile: &DeviceSplitProfile,
+) -> Result<SplitSelection, String> {
+    let abi_splits: Vec<&str> = splits
+        .iter()
+        .filter_map(|s| match &s.kind {
+            SplitKind::Abi(abi) => Some(abi.as_str()),
+            _ => None,
+        })
+        .collect();
+    let chosen_abi = if abi_splits.is_empty() || profile.abis.is_empty() {
+        None
+    } else {
+        let abi = profile
+            .abis
+            .iter()
+            .find(|a| abi_splits.contains(&a.as_str()))
+            .ok_or_else(|| {
+                format!(
+                    "No compatible ABI split: backup has [{}], device supports [{}]",
+                    abi_splits.join(", "),
+                    profile.abis.join(", ")
+                )
+            })?;
+        Some(abi.clone())
+    };
+
+    let mut densities: Vec<u32> = splits
+        .iter()
+        .filter_map(|s| match s.kind {
+            SplitKind::Density(dpi) => Some(dpi),
+            _ => None,
+        })
+        .collect();
+    densities.sort_unstable();
+    let chosen_density = profile.density_dpi.and_then(|device_dpi| {
+        densities
+            .iter()
+            .find(|&&dpi| dpi >= device_dpi)
+            .or(densities.last())
+            .copied()
+    });
+
+    let mut selection = SplitSelection {
+        selected: Vec::new(),
+        excluded: Vec::new(),
+    };
+    for split in splits {
+        let keep = match &split.kind {
+            SplitKind::Base | SplitKind::Other => true,
+            SplitKind::Abi(abi) => chosen_abi.as_ref().map_or(true, |c| c == abi),
+            SplitKind::Density(dpi) => chosen_density.map_or(true, |c| c == *dpi),
+            SplitKind::Language(lang) => {
+                profile.languages.is_empty() || profile.languages.contains(lang)
+            }
+        };
+        if keep {
+            selection.selected.push(split.path.clone());
+        } else {
+            selection.excluded.push(split.file_name.clone());
+        }
+    }
+
+    Ok(selection)
+}
+
+/// Analyzes `apk_paths` and selects the splits compatible with `device_id`.
+pub fn select_for_device(device_id: &str, apk_paths: &[PathBuf]) -> Result<SplitSelection, String> {
+    if apk_paths.len() <= 1 {
+        return Ok(SplitSelection {
+            selected: apk_paths.to_vec(),
+            excluded: Vec::new(),
+        });
+    }
+    let splits = analyze_splits(apk_paths);
+    select_splits(&splits, &query_device_profile(device_id))
+}
@@ -3,7 +3,7 @@ import { invoke } from '@tauri-apps/api/core';
 import { useApp } from '../context/AppContext';
 import { usePackages } from './usePackages';
 import { useBackups } from './useBackups';
-import { PackageInfo, BackupFile, ProgressState } from '../types/adb';
+import { PackageInfo, BackupFile, ProgressState, RestoreReport } from '../types/adb';
 import { playSuccessSound, sendOSNotification } from '../lib/feedback';
 
 /**
@@ -312,7 +312,7 @@ export function useBackupOperations(
             }));
 
             try {
-                await invoke<string>("perform_restore", {
+                await invoke<RestoreReport>("perform_restore", {
                     deviceId,
                     backupPath: file.path
                 });
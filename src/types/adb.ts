@@ -36,6 +36,423 @@ export interface BackupFile {
     path: string;
     size: number;
     date: string;
+    package_name?: string | null;
+    app?: BackupAppDetails | null;
+    source_device?: BackupSourceDevice | null;
+}
+
+/**
+ * Yedek içindeki uygulama detayları (metadata.json -> "app").
+ * IMPORTANT: Keep this in sync with `src-tauri/src/package_info.rs` -> `PackageDetails`
+ */
+export interface BackupAppDetails {
+    versionCode?: number | null;
+    versionName?: string | null;
+    minSdk?: number | null;
+    targetSdk?: number | null;
+    installerPackage?: string | null;
+    firstInstallTime?: string | null;
+    lastUpdateTime?: string | null;
+    signingCertSha256?: string | null;
+    requestedPermissions: string[];
+    nativeAbis: string[];
+    allowBackup?: boolean | null;
+}
+
+/**
+ * Yedeğin alındığı cihaz (metadata.json -> "sourceDevice").
+ * IMPORTANT: Keep this in sync with `src-tauri/src/package_info.rs` -> `SourceDevice`
+ */
+export interface BackupSourceDevice {
+    serial?: string | null;
+    model?: string | null;
+    manufacturer?: string | null;
+    fingerprint?: string | null;
+    sdk?: number | null;
+}
+
+/**
+ * Yedek manifestosu (metadata.json, format v4).
+ * IMPORTANT: Keep this in sync with `src-tauri/src/backup_manifest.rs` -> `BackupManifest`
+ */
+export interface BackupManifest {
+    formatVersion: number;
+    createdBy?: string | null;
+    packageName: string;
+    originalPath?: string | null;
+    backupDate?: string | null; // RFC 3339
+    isSplit: boolean;
+    hasObb: boolean;
+    hasData: boolean;
+    hasDeData: boolean;
+    dataUser?: number | null;
+    hasAdbData: boolean;
+    adbBackupVersion?: number | null;
+    hasExternalData: boolean;
+    hasPermissions: boolean;
+    externalDataSize?: number | null;
+    externalDataExcludes: string[];
+    app: BackupAppDetails;
+    sourceDevice?: BackupSourceDevice | null;
+}
+
+/**
+ * Yedek kataloğundaki kayıt (metadata.json'dan indekslenir).
+ * IMPORTANT: Keep this in sync with `src-tauri/src/backup_catalog.rs` -> `CatalogEntry`
+ */
+export interface CatalogEntry {
+    path: string;
+    name: string;
+    size: number;
+    modified: number;
+    backup_date: string; // RFC 3339
+    backup_timestamp: number;
+    package_name?: string | null;
+    has_data: boolean;
+    has_obb: boolean;
+    has_external_data: boolean;
+    app?: BackupAppDetails | null;
+    source_device?: BackupSourceDevice | null;
+}
+
+/**
+ * Katalog sorgusu; verilmeyen alanlar filtrelemez.
+ */
+export interface CatalogQuery {
+    package_name?: string;
+    device?: string; // serial or model
+    has_data?: boolean;
+    oldest_first?: boolean;
+}
+
+/**
+ * Yedek içeriği (restore etmeden inceleme).
+ * IMPORTANT: Keep this in sync with `src-tauri/src/backup_inspect.rs` -> `BackupInspection`
+ */
+export interface BackupInspection {
+    path: string;
+    size: number;
+    metadata?: BackupManifest | null;
+    metadata_error?: string | null;
+    entries: { name: string; size: number; compressed_size: number; is_dir: boolean }[];
+    splits: {
+        file_name: string;
+        split_name?: string | null;
+        kind:
+            | { type: 'base' | 'other' }
+            | { type: 'abi' | 'language'; value: string }
+            | { type: 'density'; value: number };
+    }[];
+    components: { id: string; size: number; file_count: number }[];
+    is_legacy: boolean;
+}
+
+/**
+ * Yedek saklama kuralları.
+ * IMPORTANT: Keep this in sync with `src-tauri/src/retention.rs`
+ */
+export interface RetentionRule {
+    keep_last?: number | null;
+    keep_weekly_weeks?: number | null;
+    max_total_size?: number | null; // bytes
+}
+
+export interface RetentionPolicy {
+    global: RetentionRule;
+    per_package: Record<string, RetentionRule>;
+}
+
+export interface RetentionPlan {
+    // Pass these paths to `apply_retention` to confirm the deletion
+    delete: {
+        path: string;
+        package_name: string;
+        backup_date: string;
+        size: number;
+        reason: 'keep_last' | 'keep_weekly' | 'max_total_size' | 'global_max_total_size';
+    }[];
+    keep_count: number;
+    freed_bytes: number;
+}
+
+export interface RetentionReport {
+    deleted: string[];
+    failed: string[];
+    freed_bytes: number;
+}
+
+/**
+ * Cihaz bazlı zamanlanmış yedekleme.
+ * IMPORTANT: Keep this in sync with `src-tauri/src/backup_scheduler.rs`
+ */
+export interface BackupSchedule {
+    serial: string;
+    enabled: boolean;
+    trigger: 'on_connect' | 'interval';
+    packages: string[]; // empty = every user app on the device
+    interval_hours: number;
+    destination?: string | null;
+    include_data: boolean;
+    include_obb: boolean;
+    include_external_data?: boolean;
+    name_template?: string | null; // e.g. "{device_model}/{label}/{versionName}_{date}"
+    last_run?: string | null;
+}
+
+export interface ScheduleRun {
+    serial: string;
+    trigger: 'connect' | 'interval' | 'manual';
+    started_at: string;
+    finished_at: string;
+    succeeded: string[];
+    failed: { package: string; error: string }[];
+}
+
+/**
+ * Toplu yedekleme kuyruğu.
+ * IMPORTANT: Keep this in sync with `src-tauri/src/backup_queue.rs`
+ */
+export type BackupJobStatus = 'running' | 'waiting_for_device' | 'password_required' | 'completed' | 'cancelled';
+
+export interface BackupJobItem {
+    package: PackageInfo;
+    status: 'pending' | 'running' | 'done' | 'failed';
+    backup_path: string | null;
+    size: number;
+    error: string | null;
+    attempts: number;
+}
+
+export interface BackupJob {
+    id: string;
+    device_id: string;
+    destination: string | null;
+    name_template: string | null;
+    encrypted_adb_backup?: boolean; // resume with the adb backup password after a restart
+    concurrency: number;
+    status: BackupJobStatus;
+    items: BackupJobItem[];
+    created_at: string;
+    finished_at: string | null;
+    elapsed_secs: number;
+}
+
+export interface BackupJobSummary {
+    job_id: string;
+    status: BackupJobStatus;
+    total: number;
+    succeeded: number;
+    pending: number;
+    failed: { package: string; error: string }[];
+    total_bytes: number;
+    elapsed_secs: number;
+}
+
+/**
+ * Toplu restore (en yeni yedek / paket, çakışma politikası ile).
+ * IMPORTANT: Keep this in sync with `src-tauri/src/restore_queue.rs`
+ */
+export type ConflictPolicy = 'skip_if_installed' | 'replace' | 'replace_if_newer' | 'data_only';
+
+export interface BulkRestoreRequest {
+    device_id: string;
+    backups?: string[];
+    packages?: string[];
+    default_policy?: ConflictPolicy;
+    policies?: Record<string, ConflictPolicy>;
+    options?: RestoreOptions;
+}
+
+export interface PlannedRestore {
+    package_name: string;
+    backup_path: string;
+    backup_date: string;
+    policy: ConflictPolicy;
+}
+
+export interface AppRestoreResult {
+    package_name: string;
+    backup_path: string;
+    policy: ConflictPolicy;
+    outcome: 'pending' | 'restored' | 'skipped' | 'failed' | 'cancelled';
+    message: string;
+    report: RestoreReport | null;
+}
+
+export interface BulkRestoreJob {
+    id: string;
+    device_id: string;
+    started_at: string;
+    finished_at: string | null;
+    cancelled: boolean;
+    results: AppRestoreResult[];
+}
+
+/**
+ * Cihazın önceki uygulama setini geri yükleme planı.
+ * IMPORTANT: Keep this in sync with `src-tauri/src/app_set.rs` -> `AppSetPlan`
+ */
+export interface AppSetPlan {
+    serial: string;
+    profile_date: string;
+    restorable: PlannedRestore[];
+    without_backup: string[];
+    job_id: string | null;
+}
+
+/**
+ * Cihazdan cihaza taşıma.
+ * IMPORTANT: Keep this in sync with `src-tauri/src/migration.rs`
+ */
+export interface MigrationCandidate {
+    package: PackageInfo;
+    label: string | null;
+    apk_size: number; // base + split APKs
+    data_size: number | null; // root only
+}
+
+export interface MigrationOptions {
+    include_obb?: boolean;
+    include_data?: boolean;
+    include_external_data?: boolean;
+    include_permissions?: boolean;
+}
+
+export interface MigrationReport {
+    source: string;
+    target: string;
+    data_migrated: boolean;
+    results: { package_name: string; success: boolean; message: string; report: RestoreReport | null }[];
+}
+
+/**
+ * Ham bölüm yedeği (root + fastboot).
+ * IMPORTANT: Keep this in sync with `src-tauri/src/partitions.rs`
+ */
+export interface PartitionInfo {
+    name: string;
+    block_name: string;
+    path: string;
+    size: number | null;
+    slot: string | null;
+}
+
+export interface PartitionImage {
+    name: string;
+    block_name: string;
+    file: string;
+    size: number;
+    sha256: string;
+    device_verified: boolean;
+    serial: string;
+    serialno?: string | null;
+    product?: string | null;
+    fingerprint: string | null;
+    slot: string | null;
+    created_at: string;
+}
+
+export interface PartitionBackupReport {
+    folder: string;
+    images: PartitionImage[];
+    failed: string[];
+}
+
+export interface PartitionProgress {
+    partition: string;
+    bytes: number;
+    total: number | null;
+}
+
+/**
+ * Rehber / SMS / arama kaydı dışa aktarımı.
+ * IMPORTANT: Keep this in sync with `src-tauri/src/personal_data.rs`
+ */
+export interface PersonalDataSelection {
+    contacts?: boolean;
+    sms?: boolean;
+    call_log?: boolean;
+}
+
+export interface PersonalDataReport {
+    path: string;
+    contacts: number | null;
+    sms: number | null;
+    call_log: number | null;
+    failed: string[];
+}
+
+/**
+ * Tekilleştirilmiş yedek deposu (chunk store).
+ * IMPORTANT: Keep this in sync with `src-tauri/src/backup_store.rs`
+ */
+export interface StoreEntry {
+    path: string;
+    size: number;
+    chunks: string[]; // SHA-256 of each chunk
+}
+
+export interface StoreSnapshot {
+    id: string;
+    package_name: string;
+    backup_date: string;
+    total_size: number;
+    new_bytes: number; // bytes actually written when the snapshot was created
+    entries: StoreEntry[];
+}
+
+export interface GcReport {
+    removed_objects: number;
+    freed_bytes: number;
+}
+
+/**
+ * Restore seçenekleri (hepsi isteğe bağlı, backend varsayılanları kullanır).
+ * IMPORTANT: Keep this in sync with `src-tauri/src/adb.rs` -> `RestoreOptions`
+ */
+export interface RestoreComponents {
+    apk?: boolean;
+    obb?: boolean;
+    data?: boolean;
+    external_data?: boolean;
+    permissions?: boolean;
+}
+
+export interface RestoreOptions {
+    allow_downgrade?: boolean; // -d
+    grant_permissions?: boolean; // -g
+    user?: number | null; // --user N
+    installer_package?: string | null; // -i
+    allow_test?: boolean; // -t
+    bypass_low_target_sdk?: boolean; // Android 14+
+    skip_if_newer_installed?: boolean;
+    install_all_splits?: boolean;
+    components?: RestoreComponents;
+}
+
+/**
+ * Restore sonucu: hangi bileşenler uygulandı / atlandı.
+ * IMPORTANT: Keep this in sync with `src-tauri/src/adb.rs` -> `RestoreReport`
+ */
+export interface RestoreReport {
+    package_name: string;
+    message: string;
+    applied: string[];
+    skipped: string[];
+    permission_failures: string[];
+}
+
+/**
+ * .apks / .xapk / .apkm paket önizlemesi.
+ * IMPORTANT: Keep this in sync with `src-tauri/src/bundle_formats.rs` -> `BundleInfo`
+ */
+export interface BundleInfo {
+    format: 'apks' | 'xapk' | 'apkm';
+    package_name?: string | null;
+    version_name?: string | null;
+    version_code?: number | null;
+    apk_files: string[];
+    expansions: { file: string; install_path: string }[];
 }
 
 /**